@@ -0,0 +1,37 @@
+//! A window that only shows its UI, like a floating meter. Only the wgpu renderer supports
+//! transparency, see `WgpuConfig::transparent` for the platforms it works on.
+
+use baseview::{Size, WindowOpenOptions, WindowScalePolicy};
+use egui::{Context, Rgba};
+use egui_baseview::{Backend, EguiWindow, GraphicsConfig, Queue, WgpuConfig};
+
+fn main() {
+    let settings = WindowOpenOptions {
+        title: String::from("egui-baseview transparent window"),
+        size: Size::new(300.0, 110.0),
+        scale: WindowScalePolicy::SystemScaleFactor,
+        #[cfg(feature = "opengl")]
+        gl_config: None,
+    };
+
+    let graphics_config = GraphicsConfig::new(Backend::wgpu(WgpuConfig {
+        transparent: true,
+        ..Default::default()
+    }));
+
+    let state = ();
+
+    EguiWindow::open_blocking(
+        settings,
+        graphics_config,
+        state,
+        |_egui_ctx: &Context, queue: &mut Queue, _state: &mut ()| {
+            queue.bg_color(Rgba::TRANSPARENT);
+        },
+        |egui_ctx: &Context, _queue: &mut Queue, _state: &mut ()| {
+            egui::Window::new("egui-baseview transparent window").show(egui_ctx, |ui| {
+                ui.label("Only this window is painted.");
+            });
+        },
+    );
+}
@@ -14,8 +14,8 @@ use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-#[cfg(not(feature = "opengl"))]
-compile_error!("There's currently no software rendering support for egui");
+#[cfg(not(any(feature = "opengl", feature = "software")))]
+compile_error!("Either the `opengl` or the `software` feature needs to be enabled");
 
 /// Re-export for convenience.
 pub use egui_baseview::*;
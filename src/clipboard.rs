@@ -0,0 +1,239 @@
+use std::{
+    error::Error,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use copypasta::ClipboardProvider;
+use thiserror::Error;
+
+/// An error from a clipboard backend.
+#[derive(Error, Debug)]
+pub enum ClipboardError {
+    /// The clipboard can't hold this kind of content on this platform.
+    #[error("this content is not supported by the clipboard on this platform")]
+    Unsupported,
+    /// The clipboard itself failed.
+    #[error(transparent)]
+    Backend(Box<dyn Error + Send + Sync>),
+}
+
+impl ClipboardError {
+    /// Wrap an error of the underlying clipboard.
+    pub fn backend(err: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self::Backend(err.into())
+    }
+}
+
+/// Where the copy and paste commands of a window go.
+///
+/// By default windows use the [`SystemClipboard`]. Use
+/// [`Queue::set_clipboard`](crate::Queue::set_clipboard) in the build closure to use another one,
+/// like a [`MemoryClipboard`] in tests, or an implementation that goes through a clipboard
+/// service of the host when a plugin isn't allowed to access the system clipboard.
+pub trait Clipboard: Send {
+    /// Get the text on the clipboard, for pasting.
+    fn get_text(&mut self) -> Result<String, ClipboardError>;
+
+    /// Put text on the clipboard.
+    fn set_text(&mut self, text: String) -> Result<(), ClipboardError>;
+
+    /// Put an image on the clipboard. Backends that can't hold images return
+    /// [`ClipboardError::Unsupported`].
+    fn set_image(&mut self, image: &egui::ColorImage) -> Result<(), ClipboardError> {
+        let _ = image;
+        Err(ClipboardError::Unsupported)
+    }
+
+    /// Get the text of the primary selection, for pasting with the middle mouse button.
+    ///
+    /// Only X11 has a primary selection. Other backends return [`ClipboardError::Unsupported`].
+    fn get_primary(&mut self) -> Result<String, ClipboardError> {
+        Err(ClipboardError::Unsupported)
+    }
+
+    /// Put selected text in the primary selection.
+    fn set_primary(&mut self, text: String) -> Result<(), ClipboardError> {
+        let _ = text;
+        Err(ClipboardError::Unsupported)
+    }
+}
+
+/// The system clipboard, through copypasta.
+///
+/// Copypasta only handles text, so images are put on the clipboard by the platform specific code
+/// below. So far this is only implemented for X11.
+pub struct SystemClipboard {
+    text: copypasta::ClipboardContext,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    image: Option<x11_clipboard::Clipboard>,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    primary:
+        Option<copypasta::x11_clipboard::X11ClipboardContext<copypasta::x11_clipboard::Primary>>,
+}
+
+impl SystemClipboard {
+    pub fn new() -> Result<Self, ClipboardError> {
+        Ok(Self {
+            text: copypasta::ClipboardContext::new().map_err(ClipboardError::backend)?,
+            #[cfg(all(unix, not(target_os = "macos")))]
+            image: None,
+            #[cfg(all(unix, not(target_os = "macos")))]
+            primary: None,
+        })
+    }
+
+    /// The connection for the primary selection is only opened once it is used.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn primary(
+        &mut self,
+    ) -> Result<
+        &mut copypasta::x11_clipboard::X11ClipboardContext<copypasta::x11_clipboard::Primary>,
+        ClipboardError,
+    > {
+        let primary = match self.primary.take() {
+            Some(primary) => primary,
+            None => copypasta::x11_clipboard::X11ClipboardContext::new()
+                .map_err(ClipboardError::backend)?,
+        };
+
+        Ok(self.primary.insert(primary))
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Result<String, ClipboardError> {
+        self.text.get_contents().map_err(ClipboardError::backend)
+    }
+
+    fn set_text(&mut self, text: String) -> Result<(), ClipboardError> {
+        self.text
+            .set_contents(text)
+            .map_err(ClipboardError::backend)
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn set_image(&mut self, image: &egui::ColorImage) -> Result<(), ClipboardError> {
+        // The connection is only opened once an image is copied, since most windows never do.
+        let clipboard = match self.image.take() {
+            Some(clipboard) => clipboard,
+            None => x11_clipboard::Clipboard::new().map_err(ClipboardError::backend)?,
+        };
+        let clipboard = self.image.insert(clipboard);
+
+        let png = encode_png(image)?;
+        let target = clipboard
+            .setter
+            .get_atom("image/png")
+            .map_err(ClipboardError::backend)?;
+
+        clipboard
+            .store(clipboard.setter.atoms.clipboard, target, png)
+            .map_err(ClipboardError::backend)
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn get_primary(&mut self) -> Result<String, ClipboardError> {
+        self.primary()?
+            .get_contents()
+            .map_err(ClipboardError::backend)
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn set_primary(&mut self, text: String) -> Result<(), ClipboardError> {
+        self.primary()?
+            .set_contents(text)
+            .map_err(ClipboardError::backend)
+    }
+}
+
+/// Encode an image as a PNG file, which is what other applications expect to paste.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn encode_png(image: &egui::ColorImage) -> Result<Vec<u8>, ClipboardError> {
+    let pixels: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+        .collect();
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, image.size[0] as u32, image.size[1] as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(ClipboardError::backend)?;
+
+    Ok(png)
+}
+
+/// A clipboard that only exists within the application.
+///
+/// Clones share the same contents, so a test can keep a clone to inspect what was copied, or to
+/// put text on the clipboard before pasting it.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryClipboard {
+    contents: Arc<Mutex<MemoryContents>>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryContents {
+    text: String,
+    primary: String,
+    image: Option<Arc<egui::ColorImage>>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn contents(&self) -> std::sync::MutexGuard<'_, MemoryContents> {
+        self.contents.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The text that was last put on the clipboard.
+    pub fn text(&self) -> String {
+        self.contents().text.clone()
+    }
+
+    /// The text that was last put in the primary selection.
+    pub fn primary(&self) -> String {
+        self.contents().primary.clone()
+    }
+
+    /// The image that was last put on the clipboard, unless text was put on it since then.
+    pub fn image(&self) -> Option<Arc<egui::ColorImage>> {
+        self.contents().image.clone()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Result<String, ClipboardError> {
+        Ok(self.text())
+    }
+
+    fn set_text(&mut self, text: String) -> Result<(), ClipboardError> {
+        let mut contents = self.contents();
+        contents.text = text;
+        contents.image = None;
+        Ok(())
+    }
+
+    fn set_image(&mut self, image: &egui::ColorImage) -> Result<(), ClipboardError> {
+        let mut contents = self.contents();
+        contents.text.clear();
+        contents.image = Some(Arc::new(image.clone()));
+        Ok(())
+    }
+
+    fn get_primary(&mut self) -> Result<String, ClipboardError> {
+        Ok(self.primary())
+    }
+
+    fn set_primary(&mut self, text: String) -> Result<(), ClipboardError> {
+        self.contents().primary = text;
+        Ok(())
+    }
+}
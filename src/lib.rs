@@ -1,10 +1,25 @@
+mod clipboard;
 mod renderer;
 mod translate;
 mod window;
 
-pub use window::{EguiWindow, KeyCapture, Queue};
+pub use window::{
+    Background, BackgroundFit, EguiWindow, EventHookAction, FrameStats, HeadlessFrame,
+    HeadlessWindow, KeyCapture, KeyCaptureFn, KeyCaptureStatus, KeyShortcut, Queue, RepaintHandle,
+    ScrollConfig, WindowRequests,
+};
 
+pub use clipboard::{Clipboard, ClipboardError, MemoryClipboard, SystemClipboard};
 pub use egui;
-pub use renderer::GraphicsConfig;
+pub use renderer::{
+    Backend, EguiRenderer, FrameBackground, GraphicsConfig, OffscreenRenderer, RendererError,
+};
 
-pub use keyboard_types::Key;
+#[cfg(feature = "opengl")]
+pub use renderer::{GlowRenderer, OpenGlConfig, OpenGlError};
+#[cfg(feature = "software")]
+pub use renderer::{SoftwareConfig, SoftwareError, SoftwareRenderer};
+#[cfg(feature = "wgpu")]
+pub use renderer::{WgpuConfig, WgpuConfiguration, WgpuError, WgpuRenderer};
+
+pub use keyboard_types::{Code, Key, KeyboardEvent, Modifiers};
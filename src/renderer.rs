@@ -1,9 +1,448 @@
+use std::{fmt, sync::Arc};
+
+use baseview::{PhySize, Window};
+use egui::{ClippedPrimitive, ColorImage, FullOutput, Rgba};
+use thiserror::Error;
+
+#[cfg(feature = "nih_log")]
+use nih_plug::log::error;
+
+#[cfg(all(feature = "tracing", not(feature = "nih_log")))]
+use tracing::error;
+
 #[cfg(feature = "opengl")]
 mod opengl;
 #[cfg(feature = "opengl")]
-pub use opengl::renderer::{GraphicsConfig, Renderer};
+pub use opengl::{
+    renderer::{GlowRenderer, OpenGlConfig},
+    OpenGlError,
+};
 
 #[cfg(feature = "wgpu")]
 mod wgpu;
 #[cfg(feature = "wgpu")]
-pub use wgpu::renderer::{GraphicsConfig, Renderer};
+pub use egui_wgpu::WgpuError;
+#[cfg(feature = "wgpu")]
+pub use wgpu::renderer::{WgpuConfig, WgpuConfiguration, WgpuRenderer};
+
+#[cfg(feature = "software")]
+mod software;
+#[cfg(feature = "software")]
+pub use software::{
+    renderer::{SoftwareConfig, SoftwareRenderer},
+    SoftwareError,
+};
+
+#[cfg(any(feature = "wgpu", feature = "software"))]
+mod raw_handle;
+
+mod offscreen;
+mod rasterizer;
+
+pub use offscreen::OffscreenRenderer;
+
+/// An error that occurred while initializing a renderer.
+#[derive(Error, Debug)]
+pub enum RendererError {
+    #[cfg(feature = "opengl")]
+    #[error(transparent)]
+    OpenGl(#[from] OpenGlError),
+    #[cfg(feature = "wgpu")]
+    #[error(transparent)]
+    Wgpu(#[from] WgpuError),
+    #[cfg(feature = "software")]
+    #[error(transparent)]
+    Software(#[from] SoftwareError),
+    /// The window is of a kind that the renderer can't draw into.
+    #[cfg(feature = "wgpu")]
+    #[error("The window's handle can't be used by wgpu: {0}")]
+    WindowHandle(#[from] raw_window_handle_06::HandleError),
+    /// An error from a custom renderer.
+    #[error(transparent)]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+    #[error("No rendering backends were configured")]
+    NoBackends,
+    /// Every backend failed to initialize. Contains the name of each backend and its error, in
+    /// the order they were tried.
+    #[error("None of the rendering backends could initialize: {}", format_failures(.0))]
+    AllBackendsFailed(Vec<(&'static str, RendererError)>),
+}
+
+impl RendererError {
+    /// Wrap the error of a custom renderer.
+    pub fn custom(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Custom(err.into())
+    }
+}
+
+fn format_failures(failures: &[(&'static str, RendererError)]) -> String {
+    failures
+        .iter()
+        .map(|(name, err)| format!("\n {name}: {err}"))
+        .collect()
+}
+
+/// A backend that paints egui's output into a baseview window.
+///
+/// The built-in backends are enabled with the `opengl`, `wgpu` and `software` features. Custom
+/// backends can be used by passing [`Backend::new`] to the [`GraphicsConfig`].
+pub trait EguiRenderer: 'static {
+    /// The configuration that is passed to [`EguiRenderer::new`].
+    type Config: Clone + Send + Sync + 'static;
+    /// The error that is returned when the renderer fails to initialize.
+    ///
+    /// Custom renderers can use [`RendererError`] directly and wrap their errors with
+    /// [`RendererError::custom`].
+    type Error: Into<RendererError>;
+
+    /// Initialize the renderer for the given window.
+    fn new(window: &Window, config: Self::Config) -> Result<Self, Self::Error>
+    where
+        Self: Sized;
+
+    /// The largest texture side that this renderer supports.
+    fn max_texture_side(&self) -> usize;
+
+    /// Paint egui's output to the window.
+    ///
+    /// The renderer is responsible for clearing the window with the background color, for
+    /// tessellating `full_output.shapes` and painting them over the background's primitives (see
+    /// [`FrameBackground::prepend`]), and for applying `full_output.textures_delta`.
+    ///
+    /// Returns whether the frame was presented. When it wasn't, for example because the surface
+    /// was lost, the next frame is painted even if it looks the same.
+    fn render(
+        &mut self,
+        window: &Window,
+        background: &FrameBackground<'_>,
+        physical_size: PhySize,
+        pixels_per_point: f32,
+        egui_ctx: &egui::Context,
+        full_output: &mut FullOutput,
+    ) -> bool;
+
+    /// Read back the frame that is painted by the next call to [`EguiRenderer::render`].
+    ///
+    /// This is used to implement [`egui::ViewportCommand::Screenshot`]. Renderers that can't read
+    /// back their output can leave this unimplemented, in which case no screenshot is delivered.
+    fn request_screenshot(&mut self) {}
+
+    /// Take the screenshot that was requested with [`EguiRenderer::request_screenshot`], if it
+    /// is ready.
+    ///
+    /// The pixels are expected to be in premultiplied sRGBA, like the ones on screen.
+    fn take_screenshot(&mut self) -> Option<ColorImage> {
+        None
+    }
+
+    /// Free the renderer's resources. This is called once when the window is about to close.
+    fn destroy(&mut self, _window: &Window) {}
+}
+
+/// An object-safe version of [`EguiRenderer`] so that [`crate::EguiWindow`] can pick the backend
+/// at runtime.
+pub(crate) trait DynRenderer {
+    fn max_texture_side(&self) -> usize;
+
+    fn render(
+        &mut self,
+        window: &Window,
+        background: &FrameBackground<'_>,
+        physical_size: PhySize,
+        pixels_per_point: f32,
+        egui_ctx: &egui::Context,
+        full_output: &mut FullOutput,
+    ) -> bool;
+
+    fn request_screenshot(&mut self);
+
+    fn take_screenshot(&mut self) -> Option<ColorImage>;
+
+    fn destroy(&mut self, window: &Window);
+}
+
+impl<R: EguiRenderer> DynRenderer for R {
+    fn max_texture_side(&self) -> usize {
+        EguiRenderer::max_texture_side(self)
+    }
+
+    fn render(
+        &mut self,
+        window: &Window,
+        background: &FrameBackground<'_>,
+        physical_size: PhySize,
+        pixels_per_point: f32,
+        egui_ctx: &egui::Context,
+        full_output: &mut FullOutput,
+    ) -> bool {
+        EguiRenderer::render(
+            self,
+            window,
+            background,
+            physical_size,
+            pixels_per_point,
+            egui_ctx,
+            full_output,
+        )
+    }
+
+    fn request_screenshot(&mut self) {
+        EguiRenderer::request_screenshot(self)
+    }
+
+    fn take_screenshot(&mut self) -> Option<ColorImage> {
+        EguiRenderer::take_screenshot(self)
+    }
+
+    fn destroy(&mut self, window: &Window) {
+        EguiRenderer::destroy(self, window)
+    }
+}
+
+/// What a renderer paints behind egui's output.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameBackground<'a> {
+    /// The color the window is cleared with. Like all [`Rgba`] colors, it is premultiplied.
+    pub color: Rgba,
+    /// The tessellated [`Background`](crate::Background), which is painted over the color. It is
+    /// only tessellated again when the background, the size of the window or its scale changes.
+    pub primitives: &'a [ClippedPrimitive],
+}
+
+impl FrameBackground<'_> {
+    /// Put the background's primitives in front of egui's `primitives`, so that they are painted
+    /// first and end up behind the UI.
+    pub fn prepend(&self, primitives: Vec<ClippedPrimitive>) -> Vec<ClippedPrimitive> {
+        if self.primitives.is_empty() {
+            return primitives;
+        }
+
+        let mut all = Vec::with_capacity(self.primitives.len() + primitives.len());
+        all.extend_from_slice(self.primitives);
+        all.extend(primitives);
+        all
+    }
+}
+
+/// Only a background color.
+impl From<Rgba> for FrameBackground<'static> {
+    fn from(color: Rgba) -> Self {
+        Self {
+            color,
+            primitives: &[],
+        }
+    }
+}
+
+/// The color to clear the window with.
+///
+/// `bg_color` is premultiplied, so it can be used as-is in a transparent window. An opaque window
+/// ignores the alpha channel, so the color is shown as if it was on top of black.
+#[cfg(any(feature = "opengl", feature = "wgpu"))]
+pub(crate) fn clear_color(bg_color: Rgba, transparent: bool) -> Rgba {
+    if transparent {
+        bg_color
+    } else {
+        Rgba::from_rgba_premultiplied(bg_color.r(), bg_color.g(), bg_color.b(), 1.0)
+    }
+}
+
+/// Used in place of a real renderer when none of the backends could initialize, so that the
+/// host keeps running. Nothing gets painted.
+pub(crate) struct NullRenderer;
+
+impl DynRenderer for NullRenderer {
+    fn max_texture_side(&self) -> usize {
+        2048
+    }
+
+    fn render(
+        &mut self,
+        _window: &Window,
+        _background: &FrameBackground<'_>,
+        _physical_size: PhySize,
+        _pixels_per_point: f32,
+        _egui_ctx: &egui::Context,
+        _full_output: &mut FullOutput,
+    ) -> bool {
+        // Trying again wouldn't paint anything either.
+        true
+    }
+
+    fn request_screenshot(&mut self) {}
+
+    fn take_screenshot(&mut self) -> Option<ColorImage> {
+        None
+    }
+
+    fn destroy(&mut self, _window: &Window) {}
+}
+
+type InitFn = dyn Fn(&Window) -> Result<Box<dyn DynRenderer>, RendererError> + Send + Sync;
+
+/// A renderer backend together with its configuration.
+#[derive(Clone)]
+pub struct Backend {
+    name: &'static str,
+    #[cfg(feature = "opengl")]
+    uses_opengl: bool,
+    init: Arc<InitFn>,
+}
+
+impl Backend {
+    /// Use a custom renderer.
+    ///
+    /// * `name` - The name of the backend. This is used in log messages.
+    /// * `config` - The configuration that is passed to [`EguiRenderer::new`].
+    ///
+    /// The window doesn't get an OpenGL context for a custom renderer unless
+    /// [`Backend::with_opengl`] is used.
+    pub fn new<R: EguiRenderer>(name: &'static str, config: R::Config) -> Self {
+        Self {
+            name,
+            #[cfg(feature = "opengl")]
+            uses_opengl: false,
+            init: Arc::new(
+                move |window| match <R as EguiRenderer>::new(window, config.clone()) {
+                    Ok(renderer) => Ok(Box::new(renderer) as Box<dyn DynRenderer>),
+                    Err(err) => Err(err.into()),
+                },
+            ),
+        }
+    }
+
+    /// Have baseview create an OpenGL context for the window, so that a custom renderer can get it
+    /// from [`Window::gl_context`](baseview::Window::gl_context).
+    #[cfg(feature = "opengl")]
+    pub fn with_opengl(mut self) -> Self {
+        self.uses_opengl = true;
+        self
+    }
+
+    /// Use the built-in OpenGL renderer.
+    #[cfg(feature = "opengl")]
+    pub fn opengl(config: OpenGlConfig) -> Self {
+        Self::new::<GlowRenderer>("OpenGL", config).with_opengl()
+    }
+
+    /// Use the built-in wgpu renderer.
+    #[cfg(feature = "wgpu")]
+    pub fn wgpu(config: WgpuConfig) -> Self {
+        Self::new::<WgpuRenderer>("wgpu", config)
+    }
+
+    /// Use the built-in software renderer.
+    #[cfg(feature = "software")]
+    pub fn software(config: SoftwareConfig) -> Self {
+        Self::new::<SoftwareRenderer>("software", config)
+    }
+
+    /// The name of the backend.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub(crate) fn init(&self, window: &Window) -> Result<Box<dyn DynRenderer>, RendererError> {
+        (self.init)(window)
+    }
+}
+
+impl fmt::Debug for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Backend")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The renderer backends to try when a window is opened.
+///
+/// The backends are tried in order, and the first one that initializes successfully is used.
+/// This makes it possible to ship a single binary that prefers wgpu and falls back to OpenGL.
+///
+/// Note that if any OpenGL backend is in the list, baseview will create an OpenGL context for the
+/// window even if an earlier backend ends up being used. That context is created once with the
+/// version from `WindowOpenOptions::gl_config`, so falling back to an older OpenGL version is done
+/// by adding OpenGL backends with a different `OpenGlConfig::shader_version`:
+///
+/// ```ignore
+/// let graphics_config = GraphicsConfig::new(Backend::opengl(OpenGlConfig::default()))
+///     .with_fallback(Backend::opengl(OpenGlConfig {
+///         shader_version: Some(egui_glow::ShaderVersion::Es100),
+///         ..Default::default()
+///     }))
+///     .with_fallback(Backend::software(SoftwareConfig::default()));
+/// ```
+///
+/// If every backend fails, the window stays open but nothing is painted. Use
+/// [`EguiWindow::try_open_parented`](crate::EguiWindow::try_open_parented) to get the error instead.
+#[derive(Debug, Clone)]
+pub struct GraphicsConfig {
+    backends: Vec<Backend>,
+}
+
+impl GraphicsConfig {
+    /// Use a single backend.
+    pub fn new(backend: Backend) -> Self {
+        Self {
+            backends: vec![backend],
+        }
+    }
+
+    /// Try the given backend if all of the previous ones failed to initialize.
+    pub fn with_fallback(mut self, backend: Backend) -> Self {
+        self.backends.push(backend);
+        self
+    }
+
+    /// The backends that will be tried, in order.
+    pub fn backends(&self) -> &[Backend] {
+        &self.backends
+    }
+
+    /// Initialize the first backend that works.
+    pub(crate) fn init(&self, window: &Window) -> Result<Box<dyn DynRenderer>, RendererError> {
+        if self.backends.is_empty() {
+            return Err(RendererError::NoBackends);
+        }
+
+        let mut failures = Vec::new();
+        for backend in &self.backends {
+            match backend.init(window) {
+                Ok(renderer) => return Ok(renderer),
+                Err(err) => {
+                    error!(
+                        "the {} backend couldn't initialize! \n {err}",
+                        backend.name()
+                    );
+                    failures.push((backend.name(), err));
+                }
+            }
+        }
+
+        Err(RendererError::AllBackendsFailed(failures))
+    }
+
+    /// Whether any of the backends needs baseview to create an OpenGL context.
+    #[cfg(feature = "opengl")]
+    pub(crate) fn uses_opengl(&self) -> bool {
+        self.backends.iter().any(|backend| backend.uses_opengl)
+    }
+}
+
+impl Default for GraphicsConfig {
+    /// All of the built-in backends that are enabled, in the order wgpu, OpenGL, software.
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut backends = Vec::new();
+
+        #[cfg(feature = "wgpu")]
+        backends.push(Backend::wgpu(Default::default()));
+        #[cfg(feature = "opengl")]
+        backends.push(Backend::opengl(Default::default()));
+        #[cfg(feature = "software")]
+        backends.push(Backend::software(Default::default()));
+
+        Self { backends }
+    }
+}
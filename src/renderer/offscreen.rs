@@ -0,0 +1,163 @@
+use baseview::PhySize;
+use egui::{Color32, ColorImage, FullOutput};
+
+use super::{
+    rasterizer::{Canvas, Rasterizer, MAX_TEXTURE_SIDE},
+    FrameBackground,
+};
+
+/// Renders egui's output into an image instead of a window.
+///
+/// This rasterizes on the CPU, so it works without a GPU or a display. This is useful for
+/// thumbnails, documentation screenshots and pixel tests. Textures are kept between calls to
+/// [`OffscreenRenderer::render`], so the output of consecutive frames can be rendered the same
+/// way a window would.
+///
+/// ```ignore
+/// let mut renderer = OffscreenRenderer::new();
+///
+/// let mut full_output = egui_ctx.run(raw_input, |ctx| {
+///     egui::CentralPanel::default().show(ctx, |ui| ui.label("Hello World!"));
+/// });
+/// let image = renderer.render(
+///     Rgba::BLACK,
+///     PhySize::new(300, 200),
+///     full_output.pixels_per_point,
+///     &egui_ctx,
+///     &mut full_output,
+/// );
+/// ```
+#[derive(Default)]
+pub struct OffscreenRenderer {
+    rasterizer: Rasterizer,
+}
+
+impl OffscreenRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The largest texture side that this renderer supports.
+    ///
+    /// Pass this to egui through `RawInput::max_texture_side`.
+    pub fn max_texture_side(&self) -> usize {
+        MAX_TEXTURE_SIDE
+    }
+
+    /// Paint egui's output into a new image.
+    ///
+    /// `background` is either a background color, or the whole [`FrameBackground`] of a
+    /// [`HeadlessWindow`](crate::HeadlessWindow). The pixels of the returned image are in
+    /// premultiplied sRGBA, like the ones on screen.
+    pub fn render<'a>(
+        &mut self,
+        background: impl Into<FrameBackground<'a>>,
+        physical_size: PhySize,
+        pixels_per_point: f32,
+        egui_ctx: &egui::Context,
+        full_output: &mut FullOutput,
+    ) -> ColorImage {
+        let PhySize {
+            width: canvas_width,
+            height: canvas_height,
+        } = physical_size;
+        let (width, height) = (canvas_width as usize, canvas_height as usize);
+        let background = background.into();
+
+        let shapes = std::mem::take(&mut full_output.shapes);
+        let textures_delta = &mut full_output.textures_delta;
+
+        for (id, image_delta) in &textures_delta.set {
+            self.rasterizer.set_texture(*id, image_delta);
+        }
+
+        let clipped_primitives = background.prepend(egui_ctx.tessellate(shapes, pixels_per_point));
+
+        let mut pixels = vec![Color32::TRANSPARENT; width * height];
+        self.rasterizer.paint(
+            &mut Canvas {
+                pixels: &mut pixels,
+                width,
+                height,
+            },
+            background.color.into(),
+            pixels_per_point,
+            &clipped_primitives,
+        );
+
+        for id in textures_delta.free.drain(..) {
+            self.rasterizer.free_texture(id);
+        }
+
+        ColorImage::new([width, height], pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{pos2, vec2, LayerId, Rect, Rgba};
+
+    use super::*;
+
+    /// Run a frame that paints a red square from (1, 1) to (3, 3) on a 4x4 screen.
+    fn red_square(egui_ctx: &egui::Context) -> FullOutput {
+        let raw_input = egui::RawInput {
+            screen_rect: Some(Rect::from_min_size(pos2(0.0, 0.0), vec2(4.0, 4.0))),
+            ..Default::default()
+        };
+
+        egui_ctx.run(raw_input, |ctx| {
+            ctx.layer_painter(LayerId::background()).rect_filled(
+                Rect::from_min_max(pos2(1.0, 1.0), pos2(3.0, 3.0)),
+                0.0,
+                Color32::RED,
+            );
+        })
+    }
+
+    fn render(
+        renderer: &mut OffscreenRenderer,
+        egui_ctx: &egui::Context,
+        full_output: &mut FullOutput,
+    ) -> ColorImage {
+        renderer.render(
+            Rgba::BLUE,
+            PhySize {
+                width: 4,
+                height: 4,
+            },
+            full_output.pixels_per_point,
+            egui_ctx,
+            full_output,
+        )
+    }
+
+    #[test]
+    fn renders_shapes_over_the_background() {
+        let egui_ctx = egui::Context::default();
+        let mut renderer = OffscreenRenderer::new();
+
+        let image = render(&mut renderer, &egui_ctx, &mut red_square(&egui_ctx));
+
+        assert_eq!(image.size, [4, 4]);
+        assert_eq!(image[(0, 0)], Color32::BLUE);
+        assert_eq!(image[(3, 3)], Color32::BLUE);
+        assert_eq!(image[(1, 1)], Color32::RED);
+        assert_eq!(image[(2, 2)], Color32::RED);
+    }
+
+    #[test]
+    fn textures_are_kept_between_frames() {
+        let egui_ctx = egui::Context::default();
+        let mut renderer = OffscreenRenderer::new();
+
+        let first = render(&mut renderer, &egui_ctx, &mut red_square(&egui_ctx));
+        let mut full_output = red_square(&egui_ctx);
+        // The font texture, which also has the white pixel that solid shapes use, was uploaded
+        // with the first frame.
+        assert!(full_output.textures_delta.set.is_empty());
+        let second = render(&mut renderer, &egui_ctx, &mut full_output);
+
+        assert_eq!(first.pixels, second.pixels);
+    }
+}
@@ -1,12 +1,24 @@
-use baseview::{PhySize, Window};
-use egui::FullOutput;
+use baseview::{gl::GlContext, PhySize, Window};
+use egui::{ColorImage, FullOutput};
 use egui_glow::Painter;
 use std::sync::Arc;
 
 use super::OpenGlError;
+use crate::renderer::{clear_color, EguiRenderer, FrameBackground};
 
+#[cfg(feature = "nih_log")]
+use nih_plug::log::error;
+
+#[cfg(all(feature = "tracing", not(feature = "nih_log")))]
+use tracing::error;
+
+/// The configuration of the OpenGL renderer.
+///
+/// OpenGL windows are always opaque. baseview picks the window's visual and pixel format itself,
+/// and doesn't pick one that a compositor blends with what is behind the window. Use
+/// `WgpuConfig::transparent` for a transparent window.
 #[derive(Debug, Clone)]
-pub struct GraphicsConfig {
+pub struct OpenGlConfig {
     /// Controls whether to apply dithering to minimize banding artifacts.
     ///
     /// Dithering assumes an sRGB output and thus will apply noise to any input value that lies between
@@ -23,7 +35,7 @@ pub struct GraphicsConfig {
     pub shader_version: Option<egui_glow::ShaderVersion>,
 }
 
-impl Default for GraphicsConfig {
+impl Default for OpenGlConfig {
     fn default() -> Self {
         Self {
             shader_version: None,
@@ -32,17 +44,21 @@ impl Default for GraphicsConfig {
     }
 }
 
-pub struct Renderer {
+/// Paints egui's output using OpenGL through [`egui_glow`].
+pub struct GlowRenderer {
     glow_context: Arc<egui_glow::glow::Context>,
     painter: Painter,
+    screenshot_requested: bool,
+    screenshot: Option<ColorImage>,
 }
 
-impl Renderer {
-    pub fn new(window: &Window, config: GraphicsConfig) -> Result<Self, OpenGlError> {
+impl EguiRenderer for GlowRenderer {
+    type Config = OpenGlConfig;
+    type Error = OpenGlError;
+
+    fn new(window: &Window, config: OpenGlConfig) -> Result<Self, OpenGlError> {
         let context = window.gl_context().ok_or(OpenGlError::NoContext)?;
-        unsafe {
-            context.make_current();
-        }
+        let _current = CurrentContext::new(context);
 
         #[allow(clippy::arc_with_non_send_sync)]
         let glow_context = Arc::new(unsafe {
@@ -57,29 +73,27 @@ impl Renderer {
         )
         .map_err(OpenGlError::CreatePainter)?;
 
-        unsafe {
-            context.make_not_current();
-        }
-
         Ok(Self {
             glow_context,
             painter,
+            screenshot_requested: false,
+            screenshot: None,
         })
     }
 
-    pub fn max_texture_side(&self) -> usize {
+    fn max_texture_side(&self) -> usize {
         self.painter.max_texture_side()
     }
 
-    pub fn render(
+    fn render(
         &mut self,
         window: &Window,
-        bg_color: egui::Rgba,
+        background: &FrameBackground<'_>,
         physical_size: PhySize,
         pixels_per_point: f32,
-        egui_ctx: &mut egui::Context,
+        egui_ctx: &egui::Context,
         full_output: &mut FullOutput,
-    ) {
+    ) -> bool {
         let PhySize {
             width: canvas_width,
             height: canvas_height,
@@ -88,15 +102,15 @@ impl Renderer {
         let shapes = std::mem::take(&mut full_output.shapes);
         let textures_delta = &mut full_output.textures_delta;
 
-        let context = window
-            .gl_context()
-            .expect("failed to get baseview gl context");
-        unsafe {
-            context.make_current();
-        }
+        let Some(context) = window.gl_context() else {
+            error!("{}", OpenGlError::NoContext);
+            return false;
+        };
+        let _current = CurrentContext::new(context);
 
         unsafe {
             use egui_glow::glow::HasContext as _;
+            let bg_color = clear_color(background.color, false);
             self.glow_context
                 .clear_color(bg_color.r(), bg_color.g(), bg_color.b(), bg_color.a());
             self.glow_context.clear(egui_glow::glow::COLOR_BUFFER_BIT);
@@ -106,7 +120,7 @@ impl Renderer {
             self.painter.set_texture(*id, image_delta);
         }
 
-        let clipped_primitives = egui_ctx.tessellate(shapes, pixels_per_point);
+        let clipped_primitives = background.prepend(egui_ctx.tessellate(shapes, pixels_per_point));
         let dimensions: [u32; 2] = [canvas_width, canvas_height];
 
         self.painter
@@ -116,15 +130,59 @@ impl Renderer {
             self.painter.free_texture(id);
         }
 
-        unsafe {
-            context.swap_buffers();
-            context.make_not_current();
+        // The back buffer is undefined after swapping, so it needs to be read before that.
+        if std::mem::take(&mut self.screenshot_requested) {
+            self.screenshot = Some(self.painter.read_screen_rgba(dimensions));
         }
+
+        context.swap_buffers();
+
+        true
+    }
+
+    fn request_screenshot(&mut self) {
+        self.screenshot_requested = true;
+    }
+
+    fn take_screenshot(&mut self) -> Option<ColorImage> {
+        self.screenshot.take()
+    }
+
+    fn destroy(&mut self, window: &Window) {
+        let Some(context) = window.gl_context() else {
+            return;
+        };
+        let _current = CurrentContext::new(context);
+
+        self.painter.destroy();
     }
 }
 
-impl Drop for Renderer {
+impl Drop for GlowRenderer {
     fn drop(&mut self) {
+        // This does nothing if the painter was already destroyed with the context made current.
         self.painter.destroy()
     }
 }
+
+/// Makes a GL context current until it is dropped, so that the context is also released when
+/// returning early.
+struct CurrentContext<'a>(&'a GlContext);
+
+impl<'a> CurrentContext<'a> {
+    fn new(context: &'a GlContext) -> Self {
+        unsafe {
+            context.make_current();
+        }
+
+        Self(context)
+    }
+}
+
+impl Drop for CurrentContext<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            self.0.make_not_current();
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! A minimal CPU rasterizer for egui's tessellated meshes.
+//!
+//! All blending is done in gamma space with premultiplied alpha, the same way egui's GPU
+//! backends do it. Textures are sampled with their magnification or minification filter, depending
+//! on whether a triangle shows them larger or smaller than they are. Mipmaps are not generated, so
+//! `TextureOptions::mipmap_mode` is ignored and heavily minified textures may alias.
+
+use std::collections::HashMap;
+
+use egui::{
+    epaint::{ClippedPrimitive, ImageDelta, Primitive, Vertex},
+    Color32, ImageData, Pos2, Rect, TextureFilter, TextureId, TextureOptions, TextureWrapMode,
+};
+
+/// The largest texture side that the rasterizer accepts.
+pub(crate) const MAX_TEXTURE_SIDE: usize = 8192;
+
+struct Texture {
+    size: [usize; 2],
+    pixels: Vec<Color32>,
+    options: TextureOptions,
+}
+
+impl Texture {
+    /// The filter to sample with when a pixel covers `texels_per_pixel` texels.
+    fn filter(&self, texels_per_pixel: f32) -> TextureFilter {
+        if texels_per_pixel > 1.0 {
+            self.options.minification
+        } else {
+            self.options.magnification
+        }
+    }
+
+    fn sample(&self, uv: Pos2, filter: TextureFilter) -> Color32 {
+        let [width, height] = self.size;
+        if width == 0 || height == 0 {
+            return Color32::TRANSPARENT;
+        }
+
+        let x = uv.x * width as f32 - 0.5;
+        let y = uv.y * height as f32 - 0.5;
+
+        match filter {
+            TextureFilter::Nearest => self.texel(x.round() as isize, y.round() as isize),
+            TextureFilter::Linear => {
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let tx = x - x0;
+                let ty = y - y0;
+                let (x0, y0) = (x0 as isize, y0 as isize);
+
+                let top = lerp_color(self.texel(x0, y0), self.texel(x0 + 1, y0), tx);
+                let bottom = lerp_color(self.texel(x0, y0 + 1), self.texel(x0 + 1, y0 + 1), tx);
+
+                lerp_color(top, bottom, ty)
+            }
+        }
+    }
+
+    fn texel(&self, x: isize, y: isize) -> Color32 {
+        let [width, height] = self.size;
+        let x = wrap(x, width, self.options.wrap_mode);
+        let y = wrap(y, height, self.options.wrap_mode);
+
+        self.pixels[y * width + x]
+    }
+}
+
+fn wrap(coord: isize, size: usize, wrap_mode: TextureWrapMode) -> usize {
+    let size = size as isize;
+
+    match wrap_mode {
+        TextureWrapMode::ClampToEdge => coord.clamp(0, size - 1) as usize,
+        TextureWrapMode::Repeat => coord.rem_euclid(size) as usize,
+        TextureWrapMode::MirroredRepeat => {
+            let period = coord.rem_euclid(2 * size);
+            if period < size {
+                period as usize
+            } else {
+                (2 * size - 1 - period) as usize
+            }
+        }
+    }
+}
+
+fn lerp_color(a: Color32, b: Color32, t: f32) -> Color32 {
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color32::from_rgba_premultiplied(
+        lerp(a.r(), b.r()),
+        lerp(a.g(), b.g()),
+        lerp(a.b(), b.b()),
+        lerp(a.a(), b.a()),
+    )
+}
+
+/// A target image for the rasterizer, in premultiplied sRGBA.
+pub(crate) struct Canvas<'a> {
+    pub(crate) pixels: &'a mut [Color32],
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+}
+
+/// Rasterizes egui's clipped primitives into a [`Canvas`].
+#[derive(Default)]
+pub(crate) struct Rasterizer {
+    textures: HashMap<TextureId, Texture>,
+}
+
+impl Rasterizer {
+    pub(crate) fn set_texture(&mut self, id: TextureId, delta: &ImageDelta) {
+        let ImageData::Color(image) = &delta.image;
+
+        match delta.pos {
+            None => {
+                self.textures.insert(
+                    id,
+                    Texture {
+                        size: image.size,
+                        pixels: image.pixels.clone(),
+                        options: delta.options,
+                    },
+                );
+            }
+            Some([x, y]) => {
+                let Some(texture) = self.textures.get_mut(&id) else {
+                    return;
+                };
+
+                let [patch_width, patch_height] = image.size;
+                let [width, height] = texture.size;
+                for row in 0..patch_height.min(height.saturating_sub(y)) {
+                    let len = patch_width.min(width.saturating_sub(x));
+                    let dst = (y + row) * width + x;
+                    let src = row * patch_width;
+                    texture.pixels[dst..dst + len].copy_from_slice(&image.pixels[src..src + len]);
+                }
+                texture.options = delta.options;
+            }
+        }
+    }
+
+    pub(crate) fn free_texture(&mut self, id: TextureId) {
+        self.textures.remove(&id);
+    }
+
+    /// Clear the canvas and paint the primitives on top of it.
+    pub(crate) fn paint(
+        &self,
+        canvas: &mut Canvas<'_>,
+        clear_color: Color32,
+        pixels_per_point: f32,
+        clipped_primitives: &[ClippedPrimitive],
+    ) {
+        canvas.pixels.fill(clear_color);
+
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in clipped_primitives
+        {
+            // Paint callbacks are backend-specific, so there is nothing we can do with them here.
+            let Primitive::Mesh(mesh) = primitive else {
+                continue;
+            };
+            let Some(texture) = self.textures.get(&mesh.texture_id) else {
+                continue;
+            };
+
+            let clip = clip_rect_in_pixels(*clip_rect, pixels_per_point, canvas);
+            if clip.is_negative() {
+                continue;
+            }
+
+            for triangle in mesh.indices.chunks_exact(3) {
+                let vertices = [
+                    mesh.vertices[triangle[0] as usize],
+                    mesh.vertices[triangle[1] as usize],
+                    mesh.vertices[triangle[2] as usize],
+                ];
+                paint_triangle(canvas, texture, clip, pixels_per_point, vertices);
+            }
+        }
+    }
+}
+
+fn clip_rect_in_pixels(clip_rect: Rect, pixels_per_point: f32, canvas: &Canvas<'_>) -> Rect {
+    let canvas_rect = Rect::from_min_max(
+        Pos2::ZERO,
+        Pos2::new(canvas.width as f32, canvas.height as f32),
+    );
+
+    Rect::from_min_max(
+        (clip_rect.min * pixels_per_point).round(),
+        (clip_rect.max * pixels_per_point).round(),
+    )
+    .intersect(canvas_rect)
+}
+
+/// The signed area of the parallelogram spanned by `a -> b` and `a -> p`.
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Whether a pixel center that lies exactly on the edge `a -> b` belongs to this triangle.
+///
+/// Two adjacent triangles traverse their shared edge in opposite directions, so exactly one of
+/// them claims the pixels on it. This prevents seams in translucent meshes.
+fn owns_edge(a: Pos2, b: Pos2) -> bool {
+    b.y > a.y || (b.y == a.y && b.x < a.x)
+}
+
+fn paint_triangle(
+    canvas: &mut Canvas<'_>,
+    texture: &Texture,
+    clip: Rect,
+    pixels_per_point: f32,
+    mut vertices: [Vertex; 3],
+) {
+    let mut pos = vertices.map(|v| v.pos * pixels_per_point);
+
+    // egui is not consistent with its winding order, so make every triangle counter-clockwise.
+    let mut area = edge(pos[0], pos[1], pos[2]);
+    if area < 0.0 {
+        vertices.swap(1, 2);
+        pos.swap(1, 2);
+        area = -area;
+    }
+    if area <= f32::EPSILON {
+        return;
+    }
+
+    let bounds = Rect::from_min_max(
+        pos[0].min(pos[1]).min(pos[2]),
+        pos[0].max(pos[1]).max(pos[2]),
+    )
+    .intersect(clip);
+    if bounds.is_negative() {
+        return;
+    }
+
+    // The texture coordinates are affine across the triangle, so the number of texels a pixel
+    // covers, and with that the filter, is the same for all of its pixels.
+    let filter = texture.filter(texels_per_pixel(
+        pos,
+        vertices.map(|v| v.uv),
+        area,
+        texture.size,
+    ));
+
+    let owned = [
+        owns_edge(pos[1], pos[2]),
+        owns_edge(pos[2], pos[0]),
+        owns_edge(pos[0], pos[1]),
+    ];
+
+    let min_x = bounds.min.x.floor().max(0.0) as usize;
+    let min_y = bounds.min.y.floor().max(0.0) as usize;
+    let max_x = (bounds.max.x.ceil() as usize).min(canvas.width);
+    let max_y = (bounds.max.y.ceil() as usize).min(canvas.height);
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+            if !clip.contains(p) {
+                continue;
+            }
+
+            let weights = [
+                edge(pos[1], pos[2], p),
+                edge(pos[2], pos[0], p),
+                edge(pos[0], pos[1], p),
+            ];
+            let inside = weights
+                .iter()
+                .zip(owned)
+                .all(|(&w, owned)| w > 0.0 || (w == 0.0 && owned));
+            if !inside {
+                continue;
+            }
+
+            let [l0, l1, l2] = weights.map(|w| w / area);
+
+            let uv = Pos2::new(
+                vertices[0].uv.x * l0 + vertices[1].uv.x * l1 + vertices[2].uv.x * l2,
+                vertices[0].uv.y * l0 + vertices[1].uv.y * l1 + vertices[2].uv.y * l2,
+            );
+            let channel = |i: usize| {
+                vertices[0].color[i] as f32 * l0
+                    + vertices[1].color[i] as f32 * l1
+                    + vertices[2].color[i] as f32 * l2
+            };
+            let texel = texture.sample(uv, filter);
+
+            // Both the vertex color and the texel are premultiplied, so they can simply be
+            // multiplied together.
+            let src = [
+                channel(0) * texel.r() as f32 / 255.0,
+                channel(1) * texel.g() as f32 / 255.0,
+                channel(2) * texel.b() as f32 / 255.0,
+                channel(3) * texel.a() as f32 / 255.0,
+            ];
+
+            let dst = &mut canvas.pixels[y * canvas.width + x];
+            *dst = blend(src, *dst);
+        }
+    }
+}
+
+/// How many texels one pixel of a counter-clockwise triangle covers along its longest axis.
+fn texels_per_pixel(pos: [Pos2; 3], uv: [Pos2; 3], area: f32, texture_size: [usize; 2]) -> f32 {
+    // The derivatives of the barycentric weights from `edge` with respect to x and y.
+    let dx = [
+        pos[1].y - pos[2].y,
+        pos[2].y - pos[0].y,
+        pos[0].y - pos[1].y,
+    ]
+    .map(|d| d / area);
+    let dy = [
+        pos[2].x - pos[1].x,
+        pos[0].x - pos[2].x,
+        pos[1].x - pos[0].x,
+    ]
+    .map(|d| d / area);
+    let derivative = |d: [f32; 3]| {
+        (
+            uv[0].x * d[0] + uv[1].x * d[1] + uv[2].x * d[2],
+            uv[0].y * d[0] + uv[1].y * d[1] + uv[2].y * d[2],
+        )
+    };
+
+    let [width, height] = texture_size.map(|side| side as f32);
+    let (du_dx, dv_dx) = derivative(dx);
+    let (du_dy, dv_dy) = derivative(dy);
+
+    (du_dx * width)
+        .hypot(dv_dx * height)
+        .max((du_dy * width).hypot(dv_dy * height))
+}
+
+/// Blend a premultiplied source color over the destination.
+fn blend(src: [f32; 4], dst: Color32) -> Color32 {
+    let inv_alpha = 1.0 - src[3] / 255.0;
+    let mix = |src: f32, dst: u8| (src + dst as f32 * inv_alpha).round().clamp(0.0, 255.0) as u8;
+
+    Color32::from_rgba_premultiplied(
+        mix(src[0], dst.r()),
+        mix(src[1], dst.g()),
+        mix(src[2], dst.b()),
+        mix(src[3], dst.a()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{epaint::Mesh, pos2, ColorImage};
+
+    use super::*;
+
+    const CLEAR: Color32 = Color32::from_rgb(0, 0, 64);
+
+    fn paint(rasterizer: &Rasterizer, size: usize, mesh: Mesh) -> Vec<Color32> {
+        let mut pixels = vec![Color32::TRANSPARENT; size * size];
+        rasterizer.paint(
+            &mut Canvas {
+                pixels: &mut pixels,
+                width: size,
+                height: size,
+            },
+            CLEAR,
+            1.0,
+            &[ClippedPrimitive {
+                clip_rect: Rect::EVERYTHING,
+                primitive: Primitive::Mesh(mesh),
+            }],
+        );
+
+        pixels
+    }
+
+    /// A 2x2 texture with black in the top left and bottom right corner, and white elsewhere.
+    fn checkerboard(options: TextureOptions) -> Rasterizer {
+        let image = ColorImage::new(
+            [2, 2],
+            vec![
+                Color32::BLACK,
+                Color32::WHITE,
+                Color32::WHITE,
+                Color32::BLACK,
+            ],
+        );
+        let mut rasterizer = Rasterizer::default();
+        rasterizer.set_texture(TextureId::User(1), &ImageDelta::full(image, options));
+
+        rasterizer
+    }
+
+    fn textured_quad(rect: Rect) -> Mesh {
+        let mut mesh = Mesh::with_texture(TextureId::User(1));
+        mesh.add_rect_with_uv(
+            rect,
+            Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+            Color32::WHITE,
+        );
+
+        mesh
+    }
+
+    #[test]
+    fn solid_rect() {
+        let mut rasterizer = Rasterizer::default();
+        rasterizer.set_texture(
+            TextureId::default(),
+            &ImageDelta::full(
+                ColorImage::filled([1, 1], Color32::WHITE),
+                TextureOptions::LINEAR,
+            ),
+        );
+
+        let mut mesh = Mesh::default();
+        mesh.add_colored_rect(
+            Rect::from_min_max(pos2(1.0, 1.0), pos2(3.0, 3.0)),
+            Color32::RED,
+        );
+        let pixels = paint(&rasterizer, 4, mesh);
+
+        for y in 0..4 {
+            for x in 0..4 {
+                let inside = (1..3).contains(&x) && (1..3).contains(&y);
+                let expected = if inside { Color32::RED } else { CLEAR };
+                assert_eq!(pixels[y * 4 + x], expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn translucent_rect_blends_over_the_clear_color() {
+        let mut rasterizer = Rasterizer::default();
+        rasterizer.set_texture(
+            TextureId::default(),
+            &ImageDelta::full(
+                ColorImage::filled([1, 1], Color32::WHITE),
+                TextureOptions::LINEAR,
+            ),
+        );
+
+        let mut mesh = Mesh::default();
+        mesh.add_colored_rect(
+            Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+            Color32::from_rgba_premultiplied(100, 0, 0, 128),
+        );
+        let pixels = paint(&rasterizer, 1, mesh);
+
+        assert_eq!(pixels[0], Color32::from_rgba_premultiplied(100, 0, 32, 255));
+    }
+
+    #[test]
+    fn textured_quad_shows_every_texel() {
+        let rasterizer = checkerboard(TextureOptions::NEAREST);
+        let pixels = paint(
+            &rasterizer,
+            2,
+            textured_quad(Rect::from_min_max(pos2(0.0, 0.0), pos2(2.0, 2.0))),
+        );
+
+        assert_eq!(
+            pixels,
+            [
+                Color32::BLACK,
+                Color32::WHITE,
+                Color32::WHITE,
+                Color32::BLACK
+            ]
+        );
+    }
+
+    #[test]
+    fn minified_textures_use_the_minification_filter() {
+        let quad = textured_quad(Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)));
+        let gray = Color32::from_rgba_premultiplied(128, 128, 128, 255);
+
+        let rasterizer = checkerboard(TextureOptions {
+            magnification: TextureFilter::Nearest,
+            minification: TextureFilter::Linear,
+            ..TextureOptions::NEAREST
+        });
+        assert_eq!(paint(&rasterizer, 1, quad.clone()), [gray]);
+
+        let rasterizer = checkerboard(TextureOptions {
+            magnification: TextureFilter::Linear,
+            minification: TextureFilter::Nearest,
+            ..TextureOptions::LINEAR
+        });
+        assert_eq!(paint(&rasterizer, 1, quad), [Color32::BLACK]);
+    }
+
+    #[test]
+    fn partial_updates_replace_part_of_a_texture() {
+        let mut rasterizer = checkerboard(TextureOptions::NEAREST);
+        rasterizer.set_texture(
+            TextureId::User(1),
+            &ImageDelta::partial(
+                [1, 0],
+                ColorImage::filled([1, 2], Color32::RED),
+                TextureOptions::NEAREST,
+            ),
+        );
+
+        let texture = &rasterizer.textures[&TextureId::User(1)];
+        assert_eq!(
+            texture.pixels,
+            [Color32::BLACK, Color32::RED, Color32::WHITE, Color32::RED]
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! This version of `baseview` uses `raw-window-handle` 0.5, while `wgpu` and `softbuffer` use
+//! 0.6, so we need to convert the window's handles ourselves.
+
+use std::{
+    num::{NonZeroIsize, NonZeroU32},
+    ptr::NonNull,
+};
+
+use baseview::Window;
+use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
+use raw_window_handle_06::{
+    AppKitDisplayHandle, AppKitWindowHandle, DisplayHandle, HandleError, HasDisplayHandle,
+    HasWindowHandle, RawDisplayHandle, RawWindowHandle, Win32WindowHandle, WindowHandle,
+    WindowsDisplayHandle, XcbDisplayHandle, XcbWindowHandle, XlibDisplayHandle, XlibWindowHandle,
+};
+
+/// The window and display handles of a baseview window.
+///
+/// The handles are only valid for as long as the window they were taken from is open.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RawHandles {
+    pub(crate) display: RawDisplayHandle,
+    pub(crate) window: RawWindowHandle,
+}
+
+impl RawHandles {
+    /// Convert the handles of `window`. This fails if the window is of a kind that the renderers
+    /// don't support.
+    pub(crate) fn new(window: &Window) -> Result<Self, HandleError> {
+        Ok(Self {
+            display: display_handle(window)?,
+            window: window_handle(window)?,
+        })
+    }
+}
+
+impl HasDisplayHandle for RawHandles {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        // SAFETY: The renderers that use these handles are dropped before the window is closed.
+        Ok(unsafe { DisplayHandle::borrow_raw(self.display) })
+    }
+}
+
+impl HasWindowHandle for RawHandles {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        // SAFETY: The renderers that use these handles are dropped before the window is closed.
+        Ok(unsafe { WindowHandle::borrow_raw(self.window) })
+    }
+}
+
+fn display_handle(window: &Window) -> Result<RawDisplayHandle, HandleError> {
+    let handle = match window.raw_display_handle() {
+        raw_window_handle::RawDisplayHandle::AppKit(_) => {
+            RawDisplayHandle::AppKit(AppKitDisplayHandle::new())
+        }
+        raw_window_handle::RawDisplayHandle::Xlib(handle) => RawDisplayHandle::Xlib(
+            XlibDisplayHandle::new(NonNull::new(handle.display), handle.screen),
+        ),
+        raw_window_handle::RawDisplayHandle::Xcb(handle) => RawDisplayHandle::Xcb(
+            XcbDisplayHandle::new(NonNull::new(handle.connection), handle.screen),
+        ),
+        raw_window_handle::RawDisplayHandle::Windows(_) => {
+            RawDisplayHandle::Windows(WindowsDisplayHandle::new())
+        }
+        _ => return Err(HandleError::NotSupported),
+    };
+
+    Ok(handle)
+}
+
+fn window_handle(window: &Window) -> Result<RawWindowHandle, HandleError> {
+    let handle = match window.raw_window_handle() {
+        raw_window_handle::RawWindowHandle::AppKit(handle) => RawWindowHandle::AppKit(
+            AppKitWindowHandle::new(NonNull::new(handle.ns_view).ok_or(HandleError::Unavailable)?),
+        ),
+        raw_window_handle::RawWindowHandle::Xlib(handle) => {
+            RawWindowHandle::Xlib(XlibWindowHandle::new(handle.window))
+        }
+        raw_window_handle::RawWindowHandle::Xcb(handle) => RawWindowHandle::Xcb(
+            XcbWindowHandle::new(NonZeroU32::new(handle.window).ok_or(HandleError::Unavailable)?),
+        ),
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            // will this work? i have no idea!
+            let mut raw_handle = Win32WindowHandle::new(
+                NonZeroIsize::new(handle.hwnd as isize).ok_or(HandleError::Unavailable)?,
+            );
+
+            raw_handle.hinstance = NonZeroIsize::new(handle.hinstance as isize);
+
+            RawWindowHandle::Win32(raw_handle)
+        }
+        _ => return Err(HandleError::NotSupported),
+    };
+
+    Ok(handle)
+}
@@ -0,0 +1,13 @@
+use raw_window_handle_06::HandleError;
+use thiserror::Error;
+
+pub mod renderer;
+
+#[derive(Error, Debug)]
+pub enum SoftwareError {
+    /// softbuffer's error can't be sent across threads, so only its message is kept.
+    #[error("Failed to create the software rendering surface: \n {0}")]
+    CreateSurface(String),
+    #[error("The window's handle can't be used for software rendering: {0}")]
+    WindowHandle(#[from] HandleError),
+}
@@ -0,0 +1,174 @@
+use std::num::NonZeroU32;
+
+use baseview::{PhySize, Window};
+use egui::{Color32, ColorImage, FullOutput};
+use softbuffer::{Context, Surface};
+
+use super::SoftwareError;
+use crate::renderer::{
+    rasterizer::{Canvas, Rasterizer, MAX_TEXTURE_SIDE},
+    raw_handle::RawHandles,
+    EguiRenderer, FrameBackground,
+};
+
+#[cfg(feature = "nih_log")]
+use nih_plug::log::error;
+
+#[cfg(all(feature = "tracing", not(feature = "nih_log")))]
+use tracing::error;
+
+/// The software renderer does not have any options yet.
+#[derive(Debug, Clone, Default)]
+pub struct SoftwareConfig {}
+
+/// Rasterizes egui's meshes on the CPU and copies the result to the window.
+pub struct SoftwareRenderer {
+    surface: Surface<RawHandles, RawHandles>,
+    rasterizer: Rasterizer,
+    pixels: Vec<Color32>,
+    width: u32,
+    height: u32,
+    screenshot_requested: bool,
+    screenshot: Option<ColorImage>,
+}
+
+impl EguiRenderer for SoftwareRenderer {
+    type Config = SoftwareConfig;
+    type Error = SoftwareError;
+
+    fn new(window: &Window, _config: SoftwareConfig) -> Result<Self, SoftwareError> {
+        let handles = RawHandles::new(window)?;
+
+        let context =
+            Context::new(handles).map_err(|err| SoftwareError::CreateSurface(err.to_string()))?;
+        let surface = Surface::new(&context, handles)
+            .map_err(|err| SoftwareError::CreateSurface(err.to_string()))?;
+
+        Ok(Self {
+            surface,
+            rasterizer: Rasterizer::default(),
+            pixels: Vec::new(),
+            width: 0,
+            height: 0,
+            screenshot_requested: false,
+            screenshot: None,
+        })
+    }
+
+    fn max_texture_side(&self) -> usize {
+        MAX_TEXTURE_SIDE
+    }
+
+    fn render(
+        &mut self,
+        _window: &Window,
+        background: &FrameBackground<'_>,
+        physical_size: PhySize,
+        pixels_per_point: f32,
+        egui_ctx: &egui::Context,
+        full_output: &mut FullOutput,
+    ) -> bool {
+        let PhySize {
+            width: canvas_width,
+            height: canvas_height,
+        } = physical_size;
+
+        let shapes = std::mem::take(&mut full_output.shapes);
+        let textures_delta = &mut full_output.textures_delta;
+
+        for (id, image_delta) in &textures_delta.set {
+            self.rasterizer.set_texture(*id, image_delta);
+        }
+
+        let clipped_primitives = background.prepend(egui_ctx.tessellate(shapes, pixels_per_point));
+
+        // There is nothing to present while the window has no size.
+        let presented = match (
+            NonZeroU32::new(canvas_width),
+            NonZeroU32::new(canvas_height),
+        ) {
+            (Some(width), Some(height)) => self.paint_and_present(
+                width,
+                height,
+                background.color.into(),
+                pixels_per_point,
+                &clipped_primitives,
+            ),
+            _ => true,
+        };
+
+        for id in textures_delta.free.drain(..) {
+            self.rasterizer.free_texture(id);
+        }
+
+        presented
+    }
+
+    fn request_screenshot(&mut self) {
+        self.screenshot_requested = true;
+    }
+
+    fn take_screenshot(&mut self) -> Option<ColorImage> {
+        self.screenshot.take()
+    }
+}
+
+impl SoftwareRenderer {
+    /// Returns whether the frame was presented.
+    fn paint_and_present(
+        &mut self,
+        width: NonZeroU32,
+        height: NonZeroU32,
+        clear_color: Color32,
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+    ) -> bool {
+        if self.width != width.get() || self.height != height.get() {
+            if let Err(err) = self.surface.resize(width, height) {
+                error!("Failed to resize the software rendering surface: {}", err);
+                return false;
+            }
+
+            self.width = width.get();
+            self.height = height.get();
+        }
+
+        let (width, height) = (width.get() as usize, height.get() as usize);
+        self.pixels.resize(width * height, Color32::TRANSPARENT);
+
+        self.rasterizer.paint(
+            &mut Canvas {
+                pixels: &mut self.pixels,
+                width,
+                height,
+            },
+            clear_color,
+            pixels_per_point,
+            clipped_primitives,
+        );
+
+        if std::mem::take(&mut self.screenshot_requested) {
+            self.screenshot = Some(ColorImage::new([width, height], self.pixels.clone()));
+        }
+
+        let mut buffer = match self.surface.buffer_mut() {
+            Ok(buffer) => buffer,
+            Err(err) => {
+                error!("Failed to get the software rendering buffer: {}", err);
+                return false;
+            }
+        };
+
+        // softbuffer expects `0RGB` pixels.
+        for (dst, src) in buffer.iter_mut().zip(&self.pixels) {
+            *dst = u32::from_be_bytes([0, src.r(), src.g(), src.b()]);
+        }
+
+        if let Err(err) = buffer.present() {
+            error!("Failed to present the software rendering buffer: {}", err);
+            return false;
+        }
+
+        true
+    }
+}
@@ -1,30 +1,32 @@
-use std::{
-    num::{NonZeroIsize, NonZeroU32},
-    ptr::NonNull,
-    sync::Arc,
-};
+use std::sync::Arc;
 
 use baseview::{PhySize, Window};
-use egui::FullOutput;
+use egui::{ColorImage, FullOutput, Rgba, TextureId, ViewportId};
 use egui_wgpu::{
+    capture::{capture_channel, CaptureReceiver, CaptureSender, CaptureState},
     wgpu::{
-        Color, CommandEncoderDescriptor, Extent3d, Instance, InstanceDescriptor,
-        RenderPassColorAttachment, RenderPassDescriptor, Surface, SurfaceConfiguration,
-        SurfaceTargetUnsafe, TextureDescriptor, TextureDimension, TextureUsages, TextureView,
-        TextureViewDescriptor,
+        Color, CommandEncoderDescriptor, CompositeAlphaMode, Extent3d, Instance,
+        InstanceDescriptor, PollType, RenderPassColorAttachment, RenderPassDescriptor, Surface,
+        SurfaceConfiguration, SurfaceTargetUnsafe, TextureDescriptor, TextureDimension,
+        TextureUsages, TextureView, TextureViewDescriptor,
     },
     RenderState, RendererOptions, ScreenDescriptor, WgpuError,
 };
-use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
-use raw_window_handle_06::{
-    AppKitDisplayHandle, AppKitWindowHandle, Win32WindowHandle, WindowsDisplayHandle,
-    XcbDisplayHandle, XcbWindowHandle, XlibDisplayHandle, XlibWindowHandle,
+
+use crate::renderer::{
+    clear_color, raw_handle::RawHandles, EguiRenderer, FrameBackground, RendererError,
 };
 
+#[cfg(feature = "nih_log")]
+use nih_plug::log::{error, warn};
+
+#[cfg(all(feature = "tracing", not(feature = "nih_log")))]
+use tracing::{error, warn};
+
 pub use egui_wgpu::WgpuConfiguration;
 
 #[derive(Debug, Clone)]
-pub struct GraphicsConfig {
+pub struct WgpuConfig {
     /// Controls whether to apply dithering to minimize banding artifacts.
     ///
     /// Dithering assumes an sRGB output and thus will apply noise to any input value that lies between
@@ -39,90 +41,69 @@ pub struct GraphicsConfig {
 
     /// Additional options for the wgpu renderer.
     pub renderer_options: RendererOptions,
+
+    /// Let the parts of the window that the background color and the UI don't cover show what is
+    /// behind the window. See [`Queue::bg_color`](crate::Queue::bg_color).
+    ///
+    /// This configures the surface with [`CompositeAlphaMode::PreMultiplied`], or with
+    /// [`CompositeAlphaMode::PostMultiplied`] when that is all the surface supports. When it
+    /// supports neither, a warning is logged and the window stays opaque.
+    ///
+    /// Only the wgpu renderer supports transparency, and not on every platform:
+    ///
+    /// - On macOS, the view's Metal layer becomes transparent. It only supports post-multiplied
+    ///   alpha, so translucent parts of the UI come out slightly darker than they should.
+    /// - On X11 and Windows, baseview creates the window without an alpha channel, so the surface
+    ///   only supports opaque alpha and the window stays opaque.
+    ///
+    /// Defaults to false.
+    pub transparent: bool,
 }
 
-impl Default for GraphicsConfig {
+impl Default for WgpuConfig {
     fn default() -> Self {
         Self {
             dithering: true,
             wgpu_options: Default::default(),
             renderer_options: Default::default(),
+            transparent: false,
         }
     }
 }
 
-pub struct Renderer {
+/// Paints egui's output using [`egui_wgpu`].
+pub struct WgpuRenderer {
     render_state: Arc<RenderState>,
     surface: Surface<'static>,
-    config: GraphicsConfig,
+    config: WgpuConfig,
+    /// Set when the window is transparent.
+    alpha_mode: Option<CompositeAlphaMode>,
     msaa_texture_view: Option<TextureView>,
     msaa_samples: u32,
     width: u32,
     height: u32,
+    screen_capture_state: Option<CaptureState>,
+    capture_tx: CaptureSender,
+    capture_rx: CaptureReceiver,
+    screenshot_requested: bool,
 }
 
-impl Renderer {
-    pub fn new(window: &Window, config: GraphicsConfig) -> Result<Self, WgpuError> {
-        let instance = Instance::new(&InstanceDescriptor::default());
+impl EguiRenderer for WgpuRenderer {
+    type Config = WgpuConfig;
+    /// Either a [`WgpuError`], or [`RendererError::WindowHandle`] when wgpu can't use the
+    /// window.
+    type Error = RendererError;
 
-        let raw_display_handle = window.raw_display_handle();
-        let raw_window_handle = window.raw_window_handle();
+    fn new(window: &Window, config: WgpuConfig) -> Result<Self, RendererError> {
+        let instance = Instance::new(&InstanceDescriptor::default());
 
+        let handles = RawHandles::new(window)?;
         let target = SurfaceTargetUnsafe::RawHandle {
-            raw_display_handle: match raw_display_handle {
-                raw_window_handle::RawDisplayHandle::AppKit(_) => {
-                    raw_window_handle_06::RawDisplayHandle::AppKit(AppKitDisplayHandle::new())
-                }
-                raw_window_handle::RawDisplayHandle::Xlib(handle) => {
-                    raw_window_handle_06::RawDisplayHandle::Xlib(XlibDisplayHandle::new(
-                        NonNull::new(handle.display),
-                        handle.screen,
-                    ))
-                }
-                raw_window_handle::RawDisplayHandle::Xcb(handle) => {
-                    raw_window_handle_06::RawDisplayHandle::Xcb(XcbDisplayHandle::new(
-                        NonNull::new(handle.connection),
-                        handle.screen,
-                    ))
-                }
-                raw_window_handle::RawDisplayHandle::Windows(_) => {
-                    raw_window_handle_06::RawDisplayHandle::Windows(WindowsDisplayHandle::new())
-                }
-                _ => todo!(),
-            },
-            raw_window_handle: match raw_window_handle {
-                raw_window_handle::RawWindowHandle::AppKit(handle) => {
-                    raw_window_handle_06::RawWindowHandle::AppKit(AppKitWindowHandle::new(
-                        NonNull::new(handle.ns_view).unwrap(),
-                    ))
-                }
-                raw_window_handle::RawWindowHandle::Xlib(handle) => {
-                    raw_window_handle_06::RawWindowHandle::Xlib(XlibWindowHandle::new(
-                        handle.window,
-                    ))
-                }
-                raw_window_handle::RawWindowHandle::Xcb(handle) => {
-                    raw_window_handle_06::RawWindowHandle::Xcb(XcbWindowHandle::new(
-                        NonZeroU32::new(handle.window).unwrap(),
-                    ))
-                }
-                raw_window_handle::RawWindowHandle::Win32(handle) => {
-                    // will this work? i have no idea!
-                    let mut raw_handle =
-                        Win32WindowHandle::new(NonZeroIsize::new(handle.hwnd as isize).unwrap());
-
-                    raw_handle.hinstance = handle
-                        .hinstance
-                        .is_null()
-                        .then(|| NonZeroIsize::new(handle.hinstance as isize).unwrap());
-
-                    raw_window_handle_06::RawWindowHandle::Win32(raw_handle)
-                }
-                _ => todo!(),
-            },
+            raw_display_handle: handles.display,
+            raw_window_handle: handles.window,
         };
 
-        let surface = unsafe { instance.create_surface_unsafe(target) }.unwrap();
+        let surface = unsafe { instance.create_surface_unsafe(target) }.map_err(WgpuError::from)?;
 
         let msaa_samples = config.renderer_options.msaa_samples;
 
@@ -133,18 +114,39 @@ impl Renderer {
             config.renderer_options,
         ))?);
 
+        let alpha_mode = if config.transparent {
+            let capabilities = surface.get_capabilities(&state.adapter);
+            let alpha_mode = transparent_alpha_mode(&capabilities.alpha_modes);
+            if alpha_mode.is_none() {
+                warn!(
+                    "The surface doesn't support a transparent alpha mode, the window won't be transparent. Supported modes: {:?}",
+                    capabilities.alpha_modes
+                );
+            }
+            alpha_mode
+        } else {
+            None
+        };
+
+        let (capture_tx, capture_rx) = capture_channel();
+
         Ok(Self {
             render_state: state,
             surface,
             config,
+            alpha_mode,
             msaa_texture_view: None,
             msaa_samples,
             width: 0,
             height: 0,
+            screen_capture_state: None,
+            capture_tx,
+            capture_rx,
+            screenshot_requested: false,
         })
     }
 
-    pub fn max_texture_side(&self) -> usize {
+    fn max_texture_side(&self) -> usize {
         self.render_state
             .as_ref()
             .device
@@ -152,71 +154,15 @@ impl Renderer {
             .max_texture_dimension_2d as usize
     }
 
-    fn configure_surface(&self, width: u32, height: u32) {
-        let usage = TextureUsages::RENDER_ATTACHMENT;
-
-        let mut surf_config = SurfaceConfiguration {
-            usage,
-            format: self.render_state.target_format,
-            present_mode: self.config.wgpu_options.present_mode,
-            view_formats: vec![self.render_state.target_format],
-            ..self
-                .surface
-                .get_default_config(&self.render_state.adapter, width, height)
-                .expect("Unsupported surface")
-        };
-
-        if let Some(desired_maximum_frame_latency) =
-            self.config.wgpu_options.desired_maximum_frame_latency
-        {
-            surf_config.desired_maximum_frame_latency = desired_maximum_frame_latency;
-        }
-
-        self.surface
-            .configure(&self.render_state.device, &surf_config);
-    }
-
-    fn resize_and_generate_msaa_view(&mut self, width: u32, height: u32) {
-        let render_state = self.render_state.as_ref();
-
-        self.width = width;
-        self.height = height;
-
-        self.configure_surface(width, height);
-
-        let texture_format = render_state.target_format;
-
-        if self.msaa_samples > 1 {
-            self.msaa_texture_view = Some(
-                render_state
-                    .device
-                    .create_texture(&TextureDescriptor {
-                        label: Some("egui_msaa_texture"),
-                        size: Extent3d {
-                            width,
-                            height,
-                            depth_or_array_layers: 1,
-                        },
-                        mip_level_count: 1,
-                        sample_count: self.msaa_samples.max(1),
-                        dimension: TextureDimension::D2,
-                        format: texture_format,
-                        usage: TextureUsages::RENDER_ATTACHMENT,
-                        view_formats: &[texture_format],
-                    })
-                    .create_view(&TextureViewDescriptor::default()),
-            );
-        }
-    }
-
-    pub fn render(
+    fn render(
         &mut self,
-        bg_color: egui::Rgba,
+        _window: &Window,
+        background: &FrameBackground<'_>,
         physical_size: PhySize,
         pixels_per_point: f32,
-        egui_ctx: &mut egui::Context,
+        egui_ctx: &egui::Context,
         full_output: &mut FullOutput,
-    ) {
+    ) -> bool {
         let PhySize {
             width: canvas_width,
             height: canvas_height,
@@ -224,7 +170,8 @@ impl Renderer {
 
         let shapes = std::mem::take(&mut full_output.shapes);
 
-        let clipped_primitives = egui_ctx.tessellate(shapes, pixels_per_point);
+        let clipped_primitives = background.prepend(egui_ctx.tessellate(shapes, pixels_per_point));
+        let bg_color = surface_clear_color(background.color, self.alpha_mode);
 
         let mut encoder =
             self.render_state
@@ -258,31 +205,48 @@ impl Renderer {
             )
         };
 
-        if self.width != canvas_width
+        if (self.width != canvas_width
             || self.height != canvas_height
-            || self.msaa_texture_view.is_none()
+            || self.msaa_texture_view.is_none())
+            && !self.resize_and_generate_msaa_view(canvas_width, canvas_height)
         {
-            self.resize_and_generate_msaa_view(canvas_width, canvas_height);
+            self.free_textures(&full_output.textures_delta.free);
+            return false;
         }
 
         let output_frame = { self.surface.get_current_texture() };
 
         let output_frame = match output_frame {
             Ok(frame) => frame,
-            Err(err) => match (self.config.wgpu_options.on_surface_error)(err) {
-                egui_wgpu::SurfaceErrorAction::SkipFrame => return,
-                egui_wgpu::SurfaceErrorAction::RecreateSurface => {
+            Err(err) => {
+                if let egui_wgpu::SurfaceErrorAction::RecreateSurface =
+                    (self.config.wgpu_options.on_surface_error)(err)
+                {
                     self.configure_surface(self.width, self.height);
-                    return;
                 }
-            },
+                self.free_textures(&full_output.textures_delta.free);
+                return false;
+            }
         };
 
+        // When capturing, the frame is rendered to a separate texture first since the surface
+        // texture can't be copied from on all platforms.
+        let capture = std::mem::take(&mut self.screenshot_requested);
+        let mut capture_buffer = None;
+
         {
             let renderer = self.render_state.renderer.read();
-            let frame_view = output_frame
-                .texture
-                .create_view(&TextureViewDescriptor::default());
+            let target_texture = if capture {
+                let capture_state = self.screen_capture_state.get_or_insert_with(|| {
+                    CaptureState::new(&self.render_state.device, &output_frame.texture)
+                });
+                capture_state.update(&self.render_state.device, &output_frame.texture);
+
+                &capture_state.texture
+            } else {
+                &output_frame.texture
+            };
+            let frame_view = target_texture.create_view(&TextureViewDescriptor::default());
 
             let (view, resolve_target) = if let Some(msaa_view) = &self.msaa_texture_view {
                 (msaa_view, Some(&frame_view))
@@ -321,19 +285,208 @@ impl Renderer {
             );
         }
 
-        {
-            let mut renderer = self.render_state.renderer.write();
-            for id in &full_output.textures_delta.free {
-                renderer.free_texture(id);
+        if capture {
+            if let Some(capture_state) = &mut self.screen_capture_state {
+                capture_buffer = Some(capture_state.copy_textures(
+                    &self.render_state.device,
+                    &output_frame,
+                    &mut encoder,
+                ));
             }
         }
 
+        self.free_textures(&full_output.textures_delta.free);
+
         let encoded = encoder.finish();
 
         self.render_state
             .queue
             .submit(user_cmd_bufs.into_iter().chain([encoded]));
 
+        if let (Some(capture_buffer), Some(capture_state)) =
+            (capture_buffer, &self.screen_capture_state)
+        {
+            capture_state.read_screen_rgba(
+                egui_ctx.clone(),
+                capture_buffer,
+                Vec::new(),
+                self.capture_tx.clone(),
+                ViewportId::ROOT,
+            );
+        }
+
         output_frame.present();
+
+        true
+    }
+
+    fn request_screenshot(&mut self) {
+        self.screenshot_requested = true;
+    }
+
+    fn take_screenshot(&mut self) -> Option<ColorImage> {
+        // The buffer is mapped asynchronously, and the callback only runs when the device is polled.
+        let _ = self.render_state.device.poll(PollType::Poll);
+
+        self.capture_rx
+            .try_recv()
+            .ok()
+            .map(|(_viewport_id, _user_data, image)| image)
+    }
+}
+
+impl WgpuRenderer {
+    /// Configure the surface for the given size. Returns `false`, after logging why, when the
+    /// surface can't be used with the adapter at that size.
+    fn configure_surface(&self, width: u32, height: u32) -> bool {
+        let Some(default_config) =
+            self.surface
+                .get_default_config(&self.render_state.adapter, width, height)
+        else {
+            error!(
+                "The wgpu surface can't be configured for a {}x{} window, skipping the frame",
+                width, height
+            );
+            return false;
+        };
+
+        let usage = TextureUsages::RENDER_ATTACHMENT;
+
+        let mut surf_config = SurfaceConfiguration {
+            usage,
+            format: self.render_state.target_format,
+            present_mode: self.config.wgpu_options.present_mode,
+            view_formats: vec![self.render_state.target_format],
+            ..default_config
+        };
+
+        if let Some(alpha_mode) = self.alpha_mode {
+            surf_config.alpha_mode = alpha_mode;
+        }
+
+        if let Some(desired_maximum_frame_latency) =
+            self.config.wgpu_options.desired_maximum_frame_latency
+        {
+            surf_config.desired_maximum_frame_latency = desired_maximum_frame_latency;
+        }
+
+        self.surface
+            .configure(&self.render_state.device, &surf_config);
+
+        true
+    }
+
+    /// Returns `false` when the surface couldn't be configured for the new size.
+    fn resize_and_generate_msaa_view(&mut self, width: u32, height: u32) -> bool {
+        if !self.configure_surface(width, height) {
+            return false;
+        }
+
+        let render_state = self.render_state.as_ref();
+
+        self.width = width;
+        self.height = height;
+
+        let texture_format = render_state.target_format;
+
+        if self.msaa_samples > 1 {
+            self.msaa_texture_view = Some(
+                render_state
+                    .device
+                    .create_texture(&TextureDescriptor {
+                        label: Some("egui_msaa_texture"),
+                        size: Extent3d {
+                            width,
+                            height,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: self.msaa_samples.max(1),
+                        dimension: TextureDimension::D2,
+                        format: texture_format,
+                        usage: TextureUsages::RENDER_ATTACHMENT,
+                        view_formats: &[texture_format],
+                    })
+                    .create_view(&TextureViewDescriptor::default()),
+            );
+        }
+
+        true
+    }
+
+    fn free_textures(&self, free: &[TextureId]) {
+        let mut renderer = self.render_state.renderer.write();
+        for id in free {
+            renderer.free_texture(id);
+        }
+    }
+}
+
+/// The alpha mode of a transparent surface, out of the modes it supports.
+///
+/// egui paints with premultiplied alpha, so that is preferred.
+fn transparent_alpha_mode(supported: &[CompositeAlphaMode]) -> Option<CompositeAlphaMode> {
+    [
+        CompositeAlphaMode::PreMultiplied,
+        CompositeAlphaMode::PostMultiplied,
+    ]
+    .into_iter()
+    .find(|alpha_mode| supported.contains(alpha_mode))
+}
+
+/// The color to clear a surface with the given alpha mode with.
+fn surface_clear_color(bg_color: Rgba, alpha_mode: Option<CompositeAlphaMode>) -> [f32; 4] {
+    match alpha_mode {
+        Some(CompositeAlphaMode::PostMultiplied) => bg_color.to_rgba_unmultiplied(),
+        Some(_) => clear_color(bg_color, true).to_array(),
+        None => clear_color(bg_color, false).to_array(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn premultiplied_alpha_is_preferred() {
+        assert_eq!(
+            transparent_alpha_mode(&[
+                CompositeAlphaMode::Opaque,
+                CompositeAlphaMode::PostMultiplied,
+                CompositeAlphaMode::PreMultiplied,
+            ]),
+            Some(CompositeAlphaMode::PreMultiplied)
+        );
+        assert_eq!(
+            transparent_alpha_mode(&[
+                CompositeAlphaMode::Opaque,
+                CompositeAlphaMode::PostMultiplied
+            ]),
+            Some(CompositeAlphaMode::PostMultiplied)
+        );
+    }
+
+    #[test]
+    fn opaque_surfaces_are_not_transparent() {
+        assert_eq!(
+            transparent_alpha_mode(&[CompositeAlphaMode::Opaque, CompositeAlphaMode::Inherit]),
+            None
+        );
+        assert_eq!(transparent_alpha_mode(&[]), None);
+    }
+
+    #[test]
+    fn clear_color_follows_the_alpha_mode() {
+        let bg_color = Rgba::from_rgba_premultiplied(0.25, 0.0, 0.5, 0.5);
+
+        assert_eq!(
+            surface_clear_color(bg_color, Some(CompositeAlphaMode::PreMultiplied)),
+            [0.25, 0.0, 0.5, 0.5]
+        );
+        assert_eq!(
+            surface_clear_color(bg_color, Some(CompositeAlphaMode::PostMultiplied)),
+            [0.5, 0.0, 1.0, 0.5]
+        );
+        assert_eq!(surface_clear_color(bg_color, None), [0.25, 0.0, 0.5, 1.0]);
     }
 }
@@ -3,10 +3,68 @@ pub(crate) fn translate_mouse_button(button: baseview::MouseButton) -> Option<eg
         baseview::MouseButton::Left => Some(egui::PointerButton::Primary),
         baseview::MouseButton::Right => Some(egui::PointerButton::Secondary),
         baseview::MouseButton::Middle => Some(egui::PointerButton::Middle),
+        baseview::MouseButton::Back => Some(egui::PointerButton::Extra1),
+        baseview::MouseButton::Forward => Some(egui::PointerButton::Extra2),
         _ => None,
     }
 }
 
+/// Translate a scroll wheel delta into the unit and delta of an `egui::Event::MouseWheel`.
+pub(crate) fn translate_scroll_delta(
+    delta: baseview::ScrollDelta,
+    modifiers: egui::Modifiers,
+    points_per_pixel: f32,
+    config: &crate::ScrollConfig,
+) -> (egui::MouseWheelUnit, egui::Vec2) {
+    let (unit, mut delta) = match delta {
+        baseview::ScrollDelta::Lines { x, y } => match config.points_per_line {
+            Some(points_per_line) => (
+                egui::MouseWheelUnit::Point,
+                egui::vec2(x, y) * points_per_line,
+            ),
+            None => (egui::MouseWheelUnit::Line, egui::vec2(x, y)),
+        },
+        baseview::ScrollDelta::Pixels { x, y } => (
+            egui::MouseWheelUnit::Point,
+            egui::vec2(x, y) * points_per_pixel,
+        ),
+    };
+
+    if cfg!(target_os = "macos") {
+        // This is still buggy in winit despite
+        // https://github.com/rust-windowing/winit/issues/1695 being closed
+        //
+        // TODO: See if this is an issue in baseview as well.
+        delta.x *= -1.0;
+    }
+
+    // macOS already sends horizontal scroll events while Shift is held.
+    if config.shift_to_horizontal && modifiers.shift && !cfg!(target_os = "macos") && delta.x == 0.0
+    {
+        delta = egui::vec2(delta.y, 0.0);
+    }
+
+    if config.invert {
+        delta = -delta;
+    }
+
+    (unit, delta)
+}
+
+/// Translate the key of a keyboard event into the key egui is told about.
+///
+/// This is the logical key, but it falls back to the physical key for layouts that don't produce
+/// latin characters, so that shortcuts like Ctrl+C still work.
+pub(crate) fn translate_key(
+    key: &keyboard_types::Key,
+    code: keyboard_types::Code,
+) -> Option<egui::Key> {
+    translate_virtual_key(key).or_else(|| translate_physical_key(code))
+}
+
+/// Translate the logical key, which depends on the keyboard layout.
+///
+/// keyboard-types only goes up to F24, so egui's F25 to F35 can't be produced.
 pub(crate) fn translate_virtual_key(key: &keyboard_types::Key) -> Option<egui::Key> {
     use egui::Key;
     use keyboard_types::Key as K;
@@ -22,61 +80,263 @@ pub(crate) fn translate_virtual_key(key: &keyboard_types::Key) -> Option<egui::K
         K::Backspace => Key::Backspace,
         K::Enter => Key::Enter,
 
-        K::Insert => Key::Insert,
+        // The insert key is labeled "Help" on Apple keyboards.
+        K::Insert | K::Help => Key::Insert,
         K::Delete => Key::Delete,
         K::Home => Key::Home,
         K::End => Key::End,
         K::PageUp => Key::PageUp,
         K::PageDown => Key::PageDown,
 
-        K::Character(s) => match s.chars().next()? {
-            ' ' => Key::Space,
-            '0' => Key::Num0,
-            '1' => Key::Num1,
-            '2' => Key::Num2,
-            '3' => Key::Num3,
-            '4' => Key::Num4,
-            '5' => Key::Num5,
-            '6' => Key::Num6,
-            '7' => Key::Num7,
-            '8' => Key::Num8,
-            '9' => Key::Num9,
-            'a' => Key::A,
-            'b' => Key::B,
-            'c' => Key::C,
-            'd' => Key::D,
-            'e' => Key::E,
-            'f' => Key::F,
-            'g' => Key::G,
-            'h' => Key::H,
-            'i' => Key::I,
-            'j' => Key::J,
-            'k' => Key::K,
-            'l' => Key::L,
-            'm' => Key::M,
-            'n' => Key::N,
-            'o' => Key::O,
-            'p' => Key::P,
-            'q' => Key::Q,
-            'r' => Key::R,
-            's' => Key::S,
-            't' => Key::T,
-            'u' => Key::U,
-            'v' => Key::V,
-            'w' => Key::W,
-            'x' => Key::X,
-            'y' => Key::Y,
-            'z' => Key::Z,
-            _ => {
+        K::Copy => Key::Copy,
+        K::Cut => Key::Cut,
+        K::Paste => Key::Paste,
+
+        K::BrowserBack => Key::BrowserBack,
+
+        K::F1 => Key::F1,
+        K::F2 => Key::F2,
+        K::F3 => Key::F3,
+        K::F4 => Key::F4,
+        K::F5 => Key::F5,
+        K::F6 => Key::F6,
+        K::F7 => Key::F7,
+        K::F8 => Key::F8,
+        K::F9 => Key::F9,
+        K::F10 => Key::F10,
+        K::F11 => Key::F11,
+        K::F12 => Key::F12,
+        K::F13 => Key::F13,
+        K::F14 => Key::F14,
+        K::F15 => Key::F15,
+        K::F16 => Key::F16,
+        K::F17 => Key::F17,
+        K::F18 => Key::F18,
+        K::F19 => Key::F19,
+        K::F20 => Key::F20,
+        K::F21 => Key::F21,
+        K::F22 => Key::F22,
+        K::F23 => Key::F23,
+        K::F24 => Key::F24,
+
+        K::Character(s) => {
+            let mut chars = s.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
                 return None;
             }
-        },
+
+            translate_character(c)?
+        }
+        _ => {
+            return None;
+        }
+    })
+}
+
+fn translate_character(c: char) -> Option<egui::Key> {
+    use egui::Key;
+
+    Some(match c {
+        ' ' => Key::Space,
+
+        ':' => Key::Colon,
+        ',' => Key::Comma,
+        '\\' => Key::Backslash,
+        '/' => Key::Slash,
+        '|' => Key::Pipe,
+        '?' => Key::Questionmark,
+        '!' => Key::Exclamationmark,
+        '[' => Key::OpenBracket,
+        ']' => Key::CloseBracket,
+        '{' => Key::OpenCurlyBracket,
+        '}' => Key::CloseCurlyBracket,
+        '`' => Key::Backtick,
+        '-' | '\u{2212}' => Key::Minus,
+        '.' => Key::Period,
+        '+' => Key::Plus,
+        '=' => Key::Equals,
+        ';' => Key::Semicolon,
+        '\'' => Key::Quote,
+
+        '0' => Key::Num0,
+        '1' => Key::Num1,
+        '2' => Key::Num2,
+        '3' => Key::Num3,
+        '4' => Key::Num4,
+        '5' => Key::Num5,
+        '6' => Key::Num6,
+        '7' => Key::Num7,
+        '8' => Key::Num8,
+        '9' => Key::Num9,
+
+        'a' | 'A' => Key::A,
+        'b' | 'B' => Key::B,
+        'c' | 'C' => Key::C,
+        'd' | 'D' => Key::D,
+        'e' | 'E' => Key::E,
+        'f' | 'F' => Key::F,
+        'g' | 'G' => Key::G,
+        'h' | 'H' => Key::H,
+        'i' | 'I' => Key::I,
+        'j' | 'J' => Key::J,
+        'k' | 'K' => Key::K,
+        'l' | 'L' => Key::L,
+        'm' | 'M' => Key::M,
+        'n' | 'N' => Key::N,
+        'o' | 'O' => Key::O,
+        'p' | 'P' => Key::P,
+        'q' | 'Q' => Key::Q,
+        'r' | 'R' => Key::R,
+        's' | 'S' => Key::S,
+        't' | 'T' => Key::T,
+        'u' | 'U' => Key::U,
+        'v' | 'V' => Key::V,
+        'w' | 'W' => Key::W,
+        'x' | 'X' => Key::X,
+        'y' | 'Y' => Key::Y,
+        'z' | 'Z' => Key::Z,
+
         _ => {
             return None;
         }
     })
 }
 
+/// Translate the physical key, named after its position on a US keyboard.
+pub(crate) fn translate_physical_key(code: keyboard_types::Code) -> Option<egui::Key> {
+    use egui::Key;
+    use keyboard_types::Code as C;
+
+    Some(match code {
+        C::ArrowDown => Key::ArrowDown,
+        C::ArrowLeft => Key::ArrowLeft,
+        C::ArrowRight => Key::ArrowRight,
+        C::ArrowUp => Key::ArrowUp,
+
+        C::Escape => Key::Escape,
+        C::Tab => Key::Tab,
+        C::Backspace | C::NumpadBackspace => Key::Backspace,
+        C::Enter | C::NumpadEnter => Key::Enter,
+        C::Space => Key::Space,
+
+        C::Insert | C::Help => Key::Insert,
+        C::Delete => Key::Delete,
+        C::Home => Key::Home,
+        C::End => Key::End,
+        C::PageUp => Key::PageUp,
+        C::PageDown => Key::PageDown,
+
+        C::Copy => Key::Copy,
+        C::Cut => Key::Cut,
+        C::Paste => Key::Paste,
+
+        C::BrowserBack => Key::BrowserBack,
+
+        C::Comma | C::NumpadComma => Key::Comma,
+        C::Backslash | C::IntlBackslash => Key::Backslash,
+        C::Slash | C::NumpadDivide => Key::Slash,
+        C::BracketLeft => Key::OpenBracket,
+        C::BracketRight => Key::CloseBracket,
+        C::Backquote => Key::Backtick,
+        C::Minus | C::NumpadSubtract => Key::Minus,
+        C::Period | C::NumpadDecimal => Key::Period,
+        C::NumpadAdd => Key::Plus,
+        C::Equal | C::NumpadEqual => Key::Equals,
+        C::Semicolon => Key::Semicolon,
+        C::Quote => Key::Quote,
+
+        C::Digit0 | C::Numpad0 => Key::Num0,
+        C::Digit1 | C::Numpad1 => Key::Num1,
+        C::Digit2 | C::Numpad2 => Key::Num2,
+        C::Digit3 | C::Numpad3 => Key::Num3,
+        C::Digit4 | C::Numpad4 => Key::Num4,
+        C::Digit5 | C::Numpad5 => Key::Num5,
+        C::Digit6 | C::Numpad6 => Key::Num6,
+        C::Digit7 | C::Numpad7 => Key::Num7,
+        C::Digit8 | C::Numpad8 => Key::Num8,
+        C::Digit9 | C::Numpad9 => Key::Num9,
+
+        C::KeyA => Key::A,
+        C::KeyB => Key::B,
+        C::KeyC => Key::C,
+        C::KeyD => Key::D,
+        C::KeyE => Key::E,
+        C::KeyF => Key::F,
+        C::KeyG => Key::G,
+        C::KeyH => Key::H,
+        C::KeyI => Key::I,
+        C::KeyJ => Key::J,
+        C::KeyK => Key::K,
+        C::KeyL => Key::L,
+        C::KeyM => Key::M,
+        C::KeyN => Key::N,
+        C::KeyO => Key::O,
+        C::KeyP => Key::P,
+        C::KeyQ => Key::Q,
+        C::KeyR => Key::R,
+        C::KeyS => Key::S,
+        C::KeyT => Key::T,
+        C::KeyU => Key::U,
+        C::KeyV => Key::V,
+        C::KeyW => Key::W,
+        C::KeyX => Key::X,
+        C::KeyY => Key::Y,
+        C::KeyZ => Key::Z,
+
+        C::F1 => Key::F1,
+        C::F2 => Key::F2,
+        C::F3 => Key::F3,
+        C::F4 => Key::F4,
+        C::F5 => Key::F5,
+        C::F6 => Key::F6,
+        C::F7 => Key::F7,
+        C::F8 => Key::F8,
+        C::F9 => Key::F9,
+        C::F10 => Key::F10,
+        C::F11 => Key::F11,
+        C::F12 => Key::F12,
+        C::F13 => Key::F13,
+        C::F14 => Key::F14,
+        C::F15 => Key::F15,
+        C::F16 => Key::F16,
+        C::F17 => Key::F17,
+        C::F18 => Key::F18,
+        C::F19 => Key::F19,
+        C::F20 => Key::F20,
+        C::F21 => Key::F21,
+        C::F22 => Key::F22,
+        C::F23 => Key::F23,
+        C::F24 => Key::F24,
+
+        _ => {
+            return None;
+        }
+    })
+}
+
+/// Translate the modifiers baseview sends along with every keyboard and mouse event.
+///
+/// On macOS `command` is the Cmd key, everywhere else it is Ctrl.
+pub(crate) fn translate_modifiers(modifiers: keyboard_types::Modifiers) -> egui::Modifiers {
+    use keyboard_types::Modifiers;
+
+    let ctrl = modifiers.contains(Modifiers::CONTROL);
+    let meta = modifiers.contains(Modifiers::META);
+
+    egui::Modifiers {
+        alt: modifiers.contains(Modifiers::ALT),
+        shift: modifiers.contains(Modifiers::SHIFT),
+        ctrl,
+        mac_cmd: cfg!(target_os = "macos") && meta,
+        command: if cfg!(target_os = "macos") {
+            meta
+        } else {
+            ctrl
+        },
+    }
+}
+
 pub(crate) fn translate_cursor_icon(cursor: egui::CursorIcon) -> baseview::MouseCursor {
     match cursor {
         egui::CursorIcon::Default => baseview::MouseCursor::Default,
@@ -116,3 +376,265 @@ pub(crate) fn translate_cursor_icon(cursor: egui::CursorIcon) -> baseview::Mouse
         egui::CursorIcon::ZoomOut => baseview::MouseCursor::ZoomOut,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use baseview::ScrollDelta;
+    use egui::{vec2, Key, MouseWheelUnit};
+    use keyboard_types::{Code, Key as K};
+
+    use super::*;
+    use crate::ScrollConfig;
+
+    #[test]
+    fn mouse_buttons() {
+        let buttons = [
+            (baseview::MouseButton::Left, egui::PointerButton::Primary),
+            (baseview::MouseButton::Right, egui::PointerButton::Secondary),
+            (baseview::MouseButton::Middle, egui::PointerButton::Middle),
+            (baseview::MouseButton::Back, egui::PointerButton::Extra1),
+            (baseview::MouseButton::Forward, egui::PointerButton::Extra2),
+        ];
+
+        for (button, expected) in buttons {
+            assert_eq!(translate_mouse_button(button), Some(expected), "{button:?}");
+        }
+    }
+
+    #[test]
+    fn lines_are_passed_on() {
+        let (unit, delta) = translate_scroll_delta(
+            ScrollDelta::Lines { x: 0.0, y: -2.0 },
+            egui::Modifiers::NONE,
+            1.0,
+            &ScrollConfig::default(),
+        );
+
+        assert_eq!(unit, MouseWheelUnit::Line);
+        assert_eq!(delta, vec2(0.0, -2.0));
+    }
+
+    #[test]
+    fn lines_are_scaled_by_points_per_line() {
+        let config = ScrollConfig {
+            points_per_line: Some(20.0),
+            ..Default::default()
+        };
+        let (unit, delta) = translate_scroll_delta(
+            ScrollDelta::Lines { x: 0.0, y: -2.0 },
+            egui::Modifiers::NONE,
+            0.5,
+            &config,
+        );
+
+        assert_eq!(unit, MouseWheelUnit::Point);
+        assert_eq!(delta, vec2(0.0, -40.0));
+    }
+
+    #[test]
+    fn pixels_are_scaled_to_points() {
+        let (unit, delta) = translate_scroll_delta(
+            ScrollDelta::Pixels { x: 0.0, y: 10.0 },
+            egui::Modifiers::NONE,
+            0.5,
+            &ScrollConfig::default(),
+        );
+
+        assert_eq!(unit, MouseWheelUnit::Point);
+        assert_eq!(delta, vec2(0.0, 5.0));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn shift_scrolls_horizontally() {
+        let (_, delta) = translate_scroll_delta(
+            ScrollDelta::Lines { x: 0.0, y: 3.0 },
+            egui::Modifiers::SHIFT,
+            1.0,
+            &ScrollConfig::default(),
+        );
+        assert_eq!(delta, vec2(3.0, 0.0));
+
+        let config = ScrollConfig {
+            shift_to_horizontal: false,
+            ..Default::default()
+        };
+        let (_, delta) = translate_scroll_delta(
+            ScrollDelta::Lines { x: 0.0, y: 3.0 },
+            egui::Modifiers::SHIFT,
+            1.0,
+            &config,
+        );
+        assert_eq!(delta, vec2(0.0, 3.0));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn shift_keeps_horizontal_scrolling() {
+        // A device that already scrolls horizontally, like a touchpad, isn't turned around.
+        let (_, delta) = translate_scroll_delta(
+            ScrollDelta::Pixels { x: 2.0, y: 3.0 },
+            egui::Modifiers::SHIFT,
+            1.0,
+            &ScrollConfig::default(),
+        );
+        assert_eq!(delta, vec2(2.0, 3.0));
+    }
+
+    #[test]
+    fn invert() {
+        let config = ScrollConfig {
+            invert: true,
+            ..Default::default()
+        };
+        let (_, delta) = translate_scroll_delta(
+            ScrollDelta::Lines { x: 0.0, y: 2.0 },
+            egui::Modifiers::NONE,
+            1.0,
+            &config,
+        );
+
+        assert_eq!(delta, vec2(0.0, -2.0));
+    }
+
+    #[test]
+    fn function_keys() {
+        let keys = [
+            (K::F1, Key::F1),
+            (K::F2, Key::F2),
+            (K::F3, Key::F3),
+            (K::F4, Key::F4),
+            (K::F5, Key::F5),
+            (K::F6, Key::F6),
+            (K::F7, Key::F7),
+            (K::F8, Key::F8),
+            (K::F9, Key::F9),
+            (K::F10, Key::F10),
+            (K::F11, Key::F11),
+            (K::F12, Key::F12),
+            (K::F13, Key::F13),
+            (K::F14, Key::F14),
+            (K::F15, Key::F15),
+            (K::F16, Key::F16),
+            (K::F17, Key::F17),
+            (K::F18, Key::F18),
+            (K::F19, Key::F19),
+            (K::F20, Key::F20),
+            (K::F21, Key::F21),
+            (K::F22, Key::F22),
+            (K::F23, Key::F23),
+            (K::F24, Key::F24),
+        ];
+
+        for (key, expected) in keys {
+            assert_eq!(translate_virtual_key(&key), Some(expected), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn punctuation() {
+        let keys = [
+            (":", Key::Colon),
+            (",", Key::Comma),
+            ("\\", Key::Backslash),
+            ("/", Key::Slash),
+            ("|", Key::Pipe),
+            ("?", Key::Questionmark),
+            ("!", Key::Exclamationmark),
+            ("[", Key::OpenBracket),
+            ("]", Key::CloseBracket),
+            ("{", Key::OpenCurlyBracket),
+            ("}", Key::CloseCurlyBracket),
+            ("`", Key::Backtick),
+            ("-", Key::Minus),
+            ("\u{2212}", Key::Minus),
+            (".", Key::Period),
+            ("+", Key::Plus),
+            ("=", Key::Equals),
+            (";", Key::Semicolon),
+            ("'", Key::Quote),
+            (" ", Key::Space),
+        ];
+
+        for (character, expected) in keys {
+            let key = K::Character(character.into());
+            assert_eq!(translate_virtual_key(&key), Some(expected), "{character:?}");
+        }
+    }
+
+    #[test]
+    fn letters_ignore_case() {
+        for (lower, upper, expected) in [("a", "A", Key::A), ("m", "M", Key::M), ("z", "Z", Key::Z)]
+        {
+            assert_eq!(
+                translate_virtual_key(&K::Character(lower.into())),
+                Some(expected)
+            );
+            assert_eq!(
+                translate_virtual_key(&K::Character(upper.into())),
+                Some(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn multi_character_strings_are_not_keys() {
+        assert_eq!(translate_virtual_key(&K::Character("ab".into())), None);
+        assert_eq!(
+            translate_virtual_key(&K::Character("\u{e9}\u{301}".into())),
+            None
+        );
+        assert_eq!(translate_virtual_key(&K::Character(String::new())), None);
+    }
+
+    #[test]
+    fn numpad_keys() {
+        let codes = [
+            (Code::Numpad0, Key::Num0),
+            (Code::Numpad5, Key::Num5),
+            (Code::Numpad9, Key::Num9),
+            (Code::NumpadAdd, Key::Plus),
+            (Code::NumpadSubtract, Key::Minus),
+            (Code::NumpadDivide, Key::Slash),
+            (Code::NumpadDecimal, Key::Period),
+            (Code::NumpadEqual, Key::Equals),
+            (Code::NumpadComma, Key::Comma),
+            (Code::NumpadEnter, Key::Enter),
+            (Code::NumpadBackspace, Key::Backspace),
+        ];
+
+        for (code, expected) in codes {
+            assert_eq!(translate_physical_key(code), Some(expected), "{code:?}");
+        }
+    }
+
+    #[test]
+    fn letter_codes() {
+        assert_eq!(translate_physical_key(Code::KeyA), Some(Key::A));
+        assert_eq!(translate_physical_key(Code::KeyQ), Some(Key::Q));
+        assert_eq!(translate_physical_key(Code::KeyZ), Some(Key::Z));
+    }
+
+    #[test]
+    fn logical_key_takes_precedence() {
+        // The key labeled Z on a German keyboard sits where Y is on a US keyboard.
+        assert_eq!(
+            translate_key(&K::Character("z".into()), Code::KeyY),
+            Some(Key::Z)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_physical_key() {
+        // Ctrl+C on a Russian layout produces a cyrillic character.
+        assert_eq!(
+            translate_key(&K::Character("\u{441}".into()), Code::KeyC),
+            Some(Key::C)
+        );
+        assert_eq!(
+            translate_key(&K::Unidentified, Code::Numpad1),
+            Some(Key::Num1)
+        );
+        assert_eq!(translate_key(&K::Unidentified, Code::Unidentified), None);
+    }
+}
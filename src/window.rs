@@ -1,64 +1,206 @@
-use std::time::Instant;
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use baseview::{
-    Event, EventStatus, PhySize, Window, WindowHandle, WindowHandler, WindowOpenOptions,
-    WindowScalePolicy,
+    Event, EventStatus, MouseCursor, PhyPoint, PhySize, Window, WindowHandle, WindowHandler,
+    WindowOpenOptions, WindowScalePolicy,
 };
-use copypasta::ClipboardProvider;
-use egui::{pos2, vec2, Pos2, Rect, Rgba, ViewportCommand};
+use egui::{pos2, vec2, CursorGrab, FullOutput, Pos2, Rect, Rgba, Vec2, ViewportCommand};
 use keyboard_types::Modifiers;
 use raw_window_handle::HasRawWindowHandle;
 
-use crate::{renderer::Renderer, GraphicsConfig};
+use crate::{
+    clipboard::{Clipboard, ClipboardError, SystemClipboard},
+    renderer::{DynRenderer, NullRenderer},
+    FrameBackground, GraphicsConfig, RendererError,
+};
 
 #[cfg(feature = "nih_log")]
-use nih_plug::log::{error, warn};
+use nih_plug::log::{debug, error, warn};
 
 #[cfg(all(feature = "tracing", not(feature = "nih_log")))]
-use tracing::{error, warn};
+use tracing::{debug, error, warn};
+
+mod background;
+mod headless;
+mod hooks;
+mod key_capture;
+mod pointer;
+mod repaint;
+mod viewports;
+
+use background::BackgroundLayer;
+pub use background::{Background, BackgroundFit};
+pub use headless::{HeadlessFrame, HeadlessWindow};
+pub use hooks::EventHookAction;
+use hooks::Hooks;
+pub use key_capture::{KeyCapture, KeyCaptureFn, KeyCaptureStatus, KeyShortcut};
+use pointer::PointerControl;
+pub use repaint::RepaintHandle;
+use repaint::RepaintSignal;
+use viewports::{NewViewport, Viewports};
 
 pub struct Queue<'a> {
-    bg_color: &'a mut Rgba,
-    close_requested: &'a mut bool,
-    physical_size: &'a mut PhySize,
-    key_capture: &'a mut KeyCapture,
+    settings: &'a mut WindowSettings,
+    repaint_signal: &'a Arc<RepaintSignal>,
+    frame_stats: FrameStats,
 }
 
 impl<'a> Queue<'a> {
     pub(crate) fn new(
-        bg_color: &'a mut Rgba,
-        close_requested: &'a mut bool,
-        physical_size: &'a mut PhySize,
-        key_capture: &'a mut KeyCapture,
+        settings: &'a mut WindowSettings,
+        repaint_signal: &'a Arc<RepaintSignal>,
+        frame_stats: FrameStats,
     ) -> Self {
         Self {
-            bg_color,
-            //renderer,
-            //repaint_requested,
-            close_requested,
-            physical_size,
-            key_capture,
+            settings,
+            repaint_signal,
+            frame_stats,
         }
     }
 
     /// Set the background color.
+    ///
+    /// The color is premultiplied, like all [`Rgba`] colors. Its alpha is only used when the wgpu
+    /// renderer was configured to be transparent with `WgpuConfig::transparent`. In an opaque
+    /// window the color is shown as if it was on top of black.
     pub fn bg_color(&mut self, bg_color: Rgba) {
-        *self.bg_color = bg_color;
+        self.settings.bg_color = bg_color;
+    }
+
+    /// Draw an image or a gradient behind the UI, on top of the background color. `None` removes
+    /// it again.
+    ///
+    /// The background stays until it is replaced, so this only needs to be called once, for
+    /// example in the build closure. An image is uploaded to the renderer once, and setting the
+    /// same background again doesn't upload it again. Images are only compared by reference, so
+    /// keep the `Arc` of an image around to set it again.
+    ///
+    /// The background is tessellated once, and only again when it changes or the window is
+    /// resized or rescaled.
+    pub fn set_background(&mut self, background: Option<Background>) {
+        self.settings.background.set(background);
     }
 
     /// Set size of the window.
     pub fn resize(&mut self, physical_size: PhySize) {
-        *self.physical_size = physical_size;
+        self.settings.physical_size = physical_size;
+    }
+
+    /// Let the user zoom the UI with the standard shortcuts: Ctrl+Plus, Ctrl+Minus and Ctrl+0, or
+    /// Cmd on macOS. The window is resized along with the zoom factor, so that the UI keeps the
+    /// same layout when it is zoomed in or out.
+    ///
+    /// This is disabled by default, so that the shortcuts reach the host. This sets
+    /// [`egui::Options::zoom_with_keyboard`], which is shared by all viewports, so only the root
+    /// window's setting is used.
+    pub fn set_zoom_mode(&mut self, enabled: bool) {
+        self.settings.zoom.enabled = enabled;
+    }
+
+    /// How much the UI is zoomed in, on top of the window's
+    /// [`WindowScalePolicy`](baseview::WindowScalePolicy).
+    pub fn zoom_factor(&self) -> f32 {
+        self.settings.zoom.factor
+    }
+
+    /// Zoom the UI in or out, for example to restore a zoom factor the user chose earlier.
+    ///
+    /// The new zoom factor is used from the next frame on. The window is only resized along with
+    /// it when the zoom mode was enabled with [`Queue::set_zoom_mode`].
+    pub fn set_zoom_factor(&mut self, zoom_factor: f32) {
+        self.settings.zoom.factor = zoom_factor;
     }
 
     /// Close the window.
     pub fn close_window(&mut self) {
-        *self.close_requested = true;
+        self.settings.close_requested = true;
     }
 
     /// Set how to handle capturing key events from the host.
     pub fn set_key_capture(&mut self, key_capture: KeyCapture) {
-        *self.key_capture = key_capture;
+        self.settings.key_capture = key_capture;
+    }
+
+    /// Set the clipboard that copy and paste go through, instead of the [`SystemClipboard`].
+    ///
+    /// When this is called in the build closure, the system clipboard is never opened.
+    pub fn set_clipboard(&mut self, clipboard: impl Clipboard + 'static) {
+        self.settings.clipboard = Some(Box::new(clipboard));
+    }
+
+    /// Enable the primary selection, which is what Linux users expect: selecting text puts it in
+    /// the primary selection, and clicking the middle mouse button pastes it into the focused
+    /// text field.
+    ///
+    /// This is disabled by default. Only X11 has a primary selection.
+    pub fn set_primary_selection(&mut self, enabled: bool) {
+        self.settings.primary_selection = enabled;
+    }
+
+    /// Open egui's deferred viewports, the ones shown with [`egui::Context::show_viewport_deferred`],
+    /// in windows of their own instead of embedding them in this window.
+    ///
+    /// This is disabled by default, since a plugin host may not expect a plugin to open top-level
+    /// windows. It is only read after the build closure, so it has to be enabled there. macOS
+    /// always embeds the viewports, since baseview can only open top-level windows on the main
+    /// thread there.
+    pub fn set_viewport_windows(&mut self, enabled: bool) {
+        self.settings.viewport_windows = enabled;
+    }
+
+    /// Set how scroll wheel events are turned into scrolling in egui.
+    pub fn set_scroll_config(&mut self, scroll_config: ScrollConfig) {
+        self.settings.scroll_config = scroll_config;
+    }
+
+    /// Lock the pointer, for example while dragging a knob.
+    ///
+    /// While the pointer is locked, the cursor is hidden and egui's pointer stays where the lock
+    /// started. The movement of the mouse is sent as [`egui::Event::MouseMoved`], which widgets
+    /// can read with [`egui::PointerState::motion`]. When the lock is released, the cursor
+    /// reappears where the locked pointer would have ended up.
+    ///
+    /// This is the same as sending `ViewportCommand::CursorGrab(CursorGrab::Locked)`. On X11 the
+    /// cursor is moved back to where the lock started, so that dragging never stops at the
+    /// edge of the screen. Other platforms can't move the cursor yet, so there the movement
+    /// stops when the hidden cursor reaches the edge of the screen.
+    pub fn set_pointer_lock(&mut self, locked: bool) {
+        self.settings.pointer_lock = locked;
+    }
+
+    /// Set a hook that sees every baseview event before it is translated for egui.
+    ///
+    /// The hook decides whether egui gets the event, and can override the [`EventStatus`] that is
+    /// reported to baseview. This replaces any previous event hook.
+    pub fn set_event_hook(&mut self, hook: impl FnMut(&Event) -> EventHookAction + Send + 'static) {
+        self.settings.hooks.event = Some(Box::new(hook));
+    }
+
+    /// Set a hook that is called with the input right before each egui pass begins.
+    ///
+    /// The hook can modify the input or push synthetic events to [`egui::RawInput::events`].
+    /// This replaces any previous input hook.
+    pub fn set_input_hook(&mut self, hook: impl FnMut(&mut egui::RawInput) + Send + 'static) {
+        self.settings.hooks.input = Some(Box::new(hook));
+    }
+
+    /// Set a hook that is called with the output right after each egui pass ends, before the
+    /// window acts on it. This replaces any previous output hook.
+    pub fn set_output_hook(&mut self, hook: impl FnMut(&FullOutput) + Send + 'static) {
+        self.settings.hooks.output = Some(Box::new(hook));
+    }
+
+    /// How many frames were painted or skipped so far.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// Get a handle that can request a repaint of this window from any thread.
+    pub fn repaint_handle(&self) -> RepaintHandle {
+        RepaintHandle::new(Arc::clone(self.repaint_signal))
     }
 }
 
@@ -86,22 +228,175 @@ impl OpenSettings {
     }
 }
 
-/// Describes how to handle capturing key events from the host.
-#[derive(Default, Debug, Clone, PartialEq)]
-pub enum KeyCapture {
-    #[default]
-    /// All keys will be captured from the host.
-    CaptureAll,
-    /// No keys will be captured from the host.
-    IgnoreAll,
-    /// Only the given keys will be captured from the host.
-    CaptureKeys(Vec<keyboard_types::Key>),
-    /// All keys except the given ones will be captured from the host.
-    IgnoreKeys(Vec<keyboard_types::Key>),
+/// Describes how scroll wheel events are turned into scrolling in egui.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollConfig {
+    /// How many points to scroll for each line of a scroll wheel.
+    ///
+    /// If this is `None`, the lines are passed on to egui, which scrolls by
+    /// [`egui::InputOptions::line_scroll_speed`].
+    pub points_per_line: Option<f32>,
+    /// Turn vertical scrolling into horizontal scrolling while Shift is held, for widgets that
+    /// read the `MouseWheel` events themselves. egui's own scroll delta already does this with
+    /// [`egui::InputOptions::horizontal_scroll_modifier`], and macOS does it on its own.
+    pub shift_to_horizontal: bool,
+    /// Scroll in the opposite direction, for "natural" scrolling on platforms that don't apply
+    /// it themselves.
+    pub invert: bool,
 }
 
-/// Handles an egui-baseview application
-pub struct EguiWindow<State, U>
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            points_per_line: None,
+            shift_to_horizontal: true,
+            invert: false,
+        }
+    }
+}
+
+/// Counts how many frames were painted.
+///
+/// A frame that is due for a repaint is skipped when its shapes, textures and screen size are the
+/// same as in the last painted frame, since painting it again wouldn't change anything.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    /// The number of frames that were tessellated, painted and presented.
+    pub painted_frames: u64,
+    /// The number of frames that were due for a repaint but were skipped because nothing changed.
+    pub skipped_frames: u64,
+}
+
+/// The parts of the last painted frame that determine what ends up in the window.
+struct PaintedFrame {
+    shapes: Vec<egui::epaint::ClippedShape>,
+    physical_size: PhySize,
+    pixels_per_point: f32,
+    bg_color: Rgba,
+    /// The [`BackgroundLayer::generation`] of the background.
+    background_generation: u64,
+}
+
+/// Changes to the host window that were requested while running a frame.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WindowRequests {
+    /// Whether the window was asked to close.
+    pub close: bool,
+    /// The new logical size of the window, if it was asked to resize.
+    pub resize: Option<baseview::Size>,
+    /// The new mouse cursor, if it changed.
+    pub mouse_cursor: Option<MouseCursor>,
+    /// Whether the window was asked to take keyboard focus.
+    pub focus: bool,
+}
+
+impl WindowRequests {
+    fn apply(&self, window: &mut Window) {
+        if let Some(size) = self.resize {
+            window.resize(size);
+        }
+
+        // TODO: Set mouse cursor for MacOS once baseview supports it.
+        #[cfg(not(target_os = "macos"))]
+        if let Some(cursor) = self.mouse_cursor {
+            window.set_mouse_cursor(cursor);
+        }
+
+        if self.focus {
+            window.focus();
+        }
+
+        if self.close {
+            window.close();
+        }
+    }
+}
+
+/// The settings of a window that can be changed through the [`Queue`].
+pub(crate) struct WindowSettings {
+    bg_color: Rgba,
+    background: BackgroundLayer,
+    close_requested: bool,
+    physical_size: PhySize,
+    key_capture: KeyCapture,
+    hooks: Hooks,
+    clipboard: Option<Box<dyn Clipboard>>,
+    /// Whether text selection and middle clicks use the primary selection.
+    primary_selection: bool,
+    scroll_config: ScrollConfig,
+    /// Whether deferred viewports get their own windows, see [`Queue::set_viewport_windows`].
+    viewport_windows: bool,
+    /// Whether the pointer should be locked, see [`Queue::set_pointer_lock`].
+    pointer_lock: bool,
+    zoom: Zoom,
+}
+
+impl WindowSettings {
+    fn new(physical_size: PhySize) -> Self {
+        Self {
+            bg_color: Rgba::BLACK,
+            background: BackgroundLayer::default(),
+            close_requested: false,
+            physical_size,
+            key_capture: KeyCapture::default(),
+            hooks: Hooks::default(),
+            clipboard: None,
+            primary_selection: false,
+            scroll_config: ScrollConfig::default(),
+            viewport_windows: false,
+            pointer_lock: false,
+            zoom: Zoom::default(),
+        }
+    }
+
+    fn frame_background(&self) -> FrameBackground<'_> {
+        FrameBackground {
+            color: self.bg_color,
+            primitives: self.background.primitives(),
+        }
+    }
+}
+
+/// The zoom settings that can be changed through the [`Queue`].
+struct Zoom {
+    /// Whether the zoom shortcuts are handled and the window is resized when the zoom factor
+    /// changes.
+    enabled: bool,
+    factor: f32,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            factor: 1.0,
+        }
+    }
+}
+
+/// The state of a locked pointer, in points.
+struct PointerLock {
+    /// Where egui's pointer stays while the pointer is locked.
+    origin: Pos2,
+    /// Where the pointer would be if it wasn't locked.
+    position: Pos2,
+}
+
+/// How far the cursor may move away from where the pointer was locked before it is moved back,
+/// in points.
+const POINTER_LOCK_WARP_DISTANCE: f32 = 50.0;
+
+/// The output of a single egui pass.
+struct FrameOutput {
+    full_output: FullOutput,
+    /// Whether the frame should be painted.
+    repaint: bool,
+}
+
+/// The part of an egui-baseview application that does not depend on a window or a renderer.
+///
+/// This is driven either by an [`EguiWindow`] or by a [`HeadlessWindow`].
+struct WindowCore<State, U>
 where
     State: 'static + Send,
     U: FnMut(&egui::Context, &mut Queue, &mut State),
@@ -112,50 +407,81 @@ where
 
     egui_ctx: egui::Context,
     viewport_id: egui::ViewportId,
+    /// Shared with the windows of egui's deferred viewports. This is `None` when viewports are
+    /// embedded in the window instead.
+    viewports: Option<Arc<Viewports>>,
     start_time: Instant,
     egui_input: egui::RawInput,
     pointer_pos_in_points: Option<egui::Pos2>,
-    current_cursor_icon: baseview::MouseCursor,
-
-    renderer: Renderer,
+    current_cursor_icon: MouseCursor,
+    settings: WindowSettings,
 
-    clipboard_ctx: Option<copypasta::ClipboardContext>,
-
-    physical_size: PhySize,
     scale_policy: WindowScalePolicy,
+    /// The size of the window as far as baseview knows. When the size in the settings differs from
+    /// it after a pass, the window is resized.
+    window_physical_size: PhySize,
+    /// The scale from the `WindowScalePolicy`.
+    native_pixels_per_point: f32,
+    /// The scale egui is drawn at, which includes the zoom factor.
     pixels_per_point: f32,
     points_per_pixel: f32,
-    bg_color: Rgba,
-    close_requested: bool,
+    /// The zoom factor `pixels_per_point` was calculated with.
+    applied_zoom_factor: f32,
     repaint_after: Option<Instant>,
-    key_capture: KeyCapture,
+    /// Repaints that were requested from other threads.
+    repaint_signal: Arc<RepaintSignal>,
+    last_painted_frame: Option<PaintedFrame>,
+    /// The frame that is being painted, until the renderer reports whether it was presented.
+    unpresented_frame: Option<PaintedFrame>,
+    frame_stats: FrameStats,
+    /// Whether a dead key or input method composition is in progress.
+    ime_composing: bool,
+
+    cursor_visible: bool,
+    pointer_lock: Option<PointerLock>,
+    /// The mouse buttons that are held down.
+    buttons_down: Vec<baseview::MouseButton>,
+    /// The last position of the cursor, which is not where egui's pointer is while it's locked.
+    last_cursor_pos: Option<Pos2>,
+    /// Whether the window can move the cursor.
+    can_warp_cursor: bool,
+    /// Where the window should move the cursor to.
+    cursor_warp: Option<Pos2>,
+    /// Whether the window should grab the pointer or release its grab.
+    pointer_grab: Option<bool>,
+    min_inner_size: Vec2,
+    max_inner_size: Vec2,
+    /// The viewport commands that were already reported as unsupported, so that the log doesn't
+    /// get flooded when a command is sent every frame.
+    reported_commands: Vec<String>,
+    /// Whether it was logged that the IME candidate window can't be placed.
+    reported_ime_placement: bool,
+
+    /// Screenshots that egui requested and that haven't been captured yet.
+    screenshot_requests: Vec<egui::UserData>,
+    /// Screenshots that were captured by the renderer but not delivered to egui yet.
+    pending_screenshots: Vec<egui::UserData>,
 }
 
-impl<State, U> EguiWindow<State, U>
+impl<State, U> WindowCore<State, U>
 where
     State: 'static + Send,
     U: FnMut(&egui::Context, &mut Queue, &mut State),
     U: 'static + Send,
 {
     fn new<B>(
-        window: &mut baseview::Window<'_>,
         open_settings: OpenSettings,
-        graphics_config: GraphicsConfig,
+        egui_ctx: egui::Context,
+        max_texture_side: usize,
+        system_clipboard: bool,
         mut build: B,
         update: U,
         mut state: State,
-    ) -> EguiWindow<State, U>
+    ) -> Self
     where
         B: FnMut(&egui::Context, &mut Queue, &mut State),
         B: 'static + Send,
     {
-        let renderer = Renderer::new(window, graphics_config).unwrap_or_else(|err| {
-            // TODO: better error log and not panicking, but that's gonna require baseview changes
-            error!("oops! the gpu backend couldn't initialize! \n {err}");
-            panic!("gpu backend failed to initialize: \n {err}")
-        });
-        let egui_ctx = egui::Context::default();
-
         // Assume scale for now until there is an event with a new one.
         let pixels_per_point = match open_settings.scale_policy {
             WindowScalePolicy::ScaleFactor(scale) => scale,
@@ -182,35 +508,31 @@ where
         let viewport_id = egui::ViewportId::default();
 
         let mut egui_input = egui::RawInput {
-            max_texture_side: Some(renderer.max_texture_side()),
+            max_texture_side: Some(max_texture_side),
             screen_rect: Some(screen_rect),
             ..Default::default()
         };
         let _ = egui_input.viewports.insert(viewport_id, viewport_info);
 
-        let mut physical_size = PhySize {
+        let physical_size = PhySize {
             width: (open_settings.logical_width * pixels_per_point as f64).round() as u32,
             height: (open_settings.logical_height * pixels_per_point as f64).round() as u32,
         };
 
-        let mut bg_color = Rgba::BLACK;
-        let mut close_requested = false;
-        let mut key_capture = KeyCapture::default();
-        let mut queue = Queue::new(
-            &mut bg_color,
-            &mut close_requested,
-            &mut physical_size,
-            &mut key_capture,
-        );
+        let mut settings = WindowSettings::new(physical_size);
+        let repaint_signal = RepaintSignal::new();
+        let mut queue = Queue::new(&mut settings, &repaint_signal, FrameStats::default());
         (build)(&egui_ctx, &mut queue, &mut state);
 
-        let clipboard_ctx = match copypasta::ClipboardContext::new() {
-            Ok(clipboard_ctx) => Some(clipboard_ctx),
-            Err(e) => {
-                error!("Failed to initialize clipboard: {}", e);
-                None
-            }
-        };
+        if settings.clipboard.is_none() && system_clipboard {
+            settings.clipboard = match SystemClipboard::new() {
+                Ok(clipboard) => Some(Box::new(clipboard) as Box<dyn Clipboard>),
+                Err(e) => {
+                    error!("Failed to initialize clipboard: {}", e);
+                    None
+                }
+            };
+        }
 
         let start_time = Instant::now();
 
@@ -220,213 +542,345 @@ where
 
             egui_ctx,
             viewport_id,
+            viewports: None,
             start_time,
             egui_input,
             pointer_pos_in_points: None,
-            current_cursor_icon: baseview::MouseCursor::Default,
-
-            renderer,
+            current_cursor_icon: MouseCursor::Default,
 
-            clipboard_ctx,
-
-            physical_size,
+            settings,
+            window_physical_size: physical_size,
+            native_pixels_per_point: pixels_per_point,
             pixels_per_point,
             points_per_pixel,
+            applied_zoom_factor: 1.0,
             scale_policy: open_settings.scale_policy,
-            bg_color,
-            close_requested,
             repaint_after: Some(start_time),
-            key_capture,
+            repaint_signal,
+            last_painted_frame: None,
+            unpresented_frame: None,
+            frame_stats: FrameStats::default(),
+            ime_composing: false,
+
+            cursor_visible: true,
+            pointer_lock: None,
+            buttons_down: Vec::new(),
+            last_cursor_pos: None,
+            can_warp_cursor: false,
+            cursor_warp: None,
+            pointer_grab: None,
+            min_inner_size: Vec2::ZERO,
+            max_inner_size: Vec2::INFINITY,
+            reported_commands: Vec::new(),
+            reported_ime_placement: false,
+
+            screenshot_requests: Vec::new(),
+            pending_screenshots: Vec::new(),
         }
     }
 
-    /// Open a new child window.
-    ///
-    /// * `parent` - The parent window.
-    /// * `settings` - The settings of the window.
-    /// * `state` - The initial state of your application.
-    /// * `build` - Called once before the first frame. Allows you to do setup code and to
-    /// call `ctx.set_fonts()`. Optional.
-    /// * `update` - Called before each frame. Here you should update the state of your
-    /// application and build the UI.
-    pub fn open_parented<P, B>(
-        parent: &P,
-        #[allow(unused_mut)] mut settings: WindowOpenOptions,
-        graphics_config: GraphicsConfig,
-        state: State,
-        build: B,
-        update: U,
-    ) -> WindowHandle
-    where
-        P: HasRawWindowHandle,
-        B: FnMut(&egui::Context, &mut Queue, &mut State),
-        B: 'static + Send,
-    {
-        #[cfg(feature = "opengl")]
-        if settings.gl_config.is_none() {
-            settings.gl_config = Some(Default::default());
+    /// Run this core's passes for `viewport_id`, and route their output through `viewports`.
+    fn attach_viewports(&mut self, viewports: Arc<Viewports>, viewport_id: egui::ViewportId) {
+        if let Some(mut viewport_info) = self.egui_input.viewports.remove(&self.viewport_id) {
+            if viewport_id != egui::ViewportId::ROOT {
+                viewport_info.parent = Some(egui::ViewportId::ROOT);
+            }
+            self.egui_input.viewports.insert(viewport_id, viewport_info);
         }
 
-        let open_settings = OpenSettings::new(&settings);
+        viewports.set_repaint_signal(viewport_id, Arc::clone(&self.repaint_signal));
 
-        Window::open_parented(
-            parent,
-            settings,
-            move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
-                EguiWindow::new(window, open_settings, graphics_config, build, update, state)
-            },
-        )
+        self.viewport_id = viewport_id;
+        self.egui_input.viewport_id = viewport_id;
+        self.viewports = Some(viewports);
     }
 
-    /// Open a new window that blocks the current thread until the window is destroyed.
+    /// Let egui wake this window, and the windows of its deferred viewports, when a repaint is
+    /// requested from another thread.
     ///
-    /// * `settings` - The settings of the window.
-    /// * `state` - The initial state of your application.
-    /// * `build` - Called once before the first frame. Allows you to do setup code and to
-    /// call `ctx.set_fonts()`. Optional.
-    /// * `update` - Called before each frame. Here you should update the state of your
-    /// application and build the UI.
-    pub fn open_blocking<B>(
-        #[allow(unused_mut)] mut settings: WindowOpenOptions,
-        graphics_config: GraphicsConfig,
-        state: State,
-        build: B,
-        update: U,
-    ) where
-        B: FnMut(&egui::Context, &mut Queue, &mut State),
-        B: 'static + Send,
-    {
-        #[cfg(feature = "opengl")]
-        if settings.gl_config.is_none() {
-            settings.gl_config = Some(Default::default());
-        }
-
-        let open_settings = OpenSettings::new(&settings);
-
-        Window::open_blocking(
-            settings,
-            move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
-                EguiWindow::new(window, open_settings, graphics_config, build, update, state)
-            },
-        )
+    /// There is only one callback per `egui::Context`, so this is only called for the root.
+    fn install_repaint_callback(&self) {
+        let viewport_id = self.viewport_id;
+        let repaint_signal = Arc::clone(&self.repaint_signal);
+        let viewports = self.viewports.clone();
+
+        self.egui_ctx.set_request_repaint_callback(move |info| {
+            if info.viewport_id == viewport_id {
+                repaint_signal.request_repaint_after(info.delay);
+            } else if let Some(viewports) = &viewports {
+                viewports.request_repaint_after(info.viewport_id, info.delay);
+            }
+        });
     }
 
-    /// Update the pressed key modifiers when a mouse event has sent a new set of modifiers.
+    /// Replace the pressed key modifiers with the ones baseview sent along with an event.
     fn update_modifiers(&mut self, modifiers: &Modifiers) {
-        self.egui_input.modifiers.alt = !(*modifiers & Modifiers::ALT).is_empty();
-        self.egui_input.modifiers.shift = !(*modifiers & Modifiers::SHIFT).is_empty();
-        self.egui_input.modifiers.command = !(*modifiers & Modifiers::CONTROL).is_empty();
+        self.egui_input.modifiers = crate::translate::translate_modifiers(*modifiers);
     }
-}
 
-impl<State, U> WindowHandler for EguiWindow<State, U>
-where
-    State: 'static + Send,
-    U: FnMut(&egui::Context, &mut Queue, &mut State),
-    U: 'static + Send,
-{
-    fn on_frame(&mut self, window: &mut Window) {
-        let Some(state) = &mut self.user_state else {
-            return;
-        };
+    /// Run the user's update function and end the egui pass.
+    ///
+    /// Returns `None` if there is no user state left to update.
+    fn run_frame(&mut self, requests: &mut WindowRequests) -> Option<FrameOutput> {
+        let state = self.user_state.as_mut()?;
+
+        let viewports = self.viewports.clone();
+        let _pass_guard = viewports.as_ref().map(|viewports| viewports.lock_pass());
 
         self.egui_input.time = Some(self.start_time.elapsed().as_secs_f64());
-        self.egui_input.screen_rect = Some(calculate_screen_rect(
-            self.physical_size,
-            self.points_per_pixel,
-        ));
+        let screen_rect = calculate_screen_rect(self.settings.physical_size, self.points_per_pixel);
+        self.egui_input.screen_rect = Some(screen_rect);
+
+        self.sync_zoom();
+
+        if let Some(hook) = &mut self.settings.hooks.input {
+            hook(&mut self.egui_input);
+        }
 
         self.egui_ctx.begin_pass(self.egui_input.take());
 
         //let mut repaint_requested = false;
-        let mut queue = Queue::new(
-            &mut self.bg_color,
-            &mut self.close_requested,
-            &mut self.physical_size,
-            &mut self.key_capture,
-        );
+        let mut queue = Queue::new(&mut self.settings, &self.repaint_signal, self.frame_stats);
 
         (self.user_update)(&self.egui_ctx, &mut queue, state);
+        self.settings.background.update_texture(&self.egui_ctx);
 
-        if self.close_requested {
-            window.close();
+        if self.settings.close_requested {
+            requests.close = true;
         }
 
         // Prevent data from being allocated every frame by storing this
         // in a member field.
         let mut full_output = self.egui_ctx.end_pass();
 
+        // The renderer paints the background before egui's own primitives, so that it ends up
+        // behind them.
+        self.settings.background.tessellate(
+            &self.egui_ctx,
+            screen_rect,
+            full_output.pixels_per_point,
+        );
+
+        if let Some(hook) = &mut self.settings.hooks.output {
+            hook(&full_output);
+        }
+
+        let commands = match &viewports {
+            Some(viewports) => viewports.after_pass(self.viewport_id, &mut full_output),
+            None => full_output
+                .viewport_output
+                .get_mut(&self.viewport_id)
+                .map(|viewport_output| std::mem::take(&mut viewport_output.commands))
+                .unwrap_or_default(),
+        };
+
         let Some(viewport_output) = full_output.viewport_output.get(&self.viewport_id) else {
-            // The main window was closed by egui.
-            window.close();
-            return;
+            // The window was closed by egui.
+            requests.close = true;
+            return Some(FrameOutput {
+                full_output,
+                repaint: false,
+            });
         };
 
-        for command in viewport_output.commands.iter() {
+        let mut size_limits_changed = false;
+        for command in commands.iter() {
             match command {
                 ViewportCommand::Close => {
-                    window.close();
+                    requests.close = true;
                 }
-                ViewportCommand::InnerSize(size) => window.resize(baseview::Size {
-                    width: size.x.max(1.0) as f64,
-                    height: size.y.max(1.0) as f64,
-                }),
-                _ => {}
+                ViewportCommand::InnerSize(size) => {
+                    requests.resize = Some(self.clamp_inner_size(*size));
+                }
+                ViewportCommand::MinInnerSize(size) => {
+                    self.min_inner_size = *size;
+                    size_limits_changed = true;
+                }
+                ViewportCommand::MaxInnerSize(size) => {
+                    self.max_inner_size = *size;
+                    size_limits_changed = true;
+                }
+                ViewportCommand::Title(title) => {
+                    if let Some(viewport_info) =
+                        self.egui_input.viewports.get_mut(&self.viewport_id)
+                    {
+                        viewport_info.title = Some(title.clone());
+                    }
+                    self.report_unsupported_command(
+                        "Title",
+                        "baseview can't change the title of an open window",
+                    );
+                }
+                ViewportCommand::Focus => {
+                    requests.focus = true;
+                }
+                ViewportCommand::CursorVisible(visible) => {
+                    self.cursor_visible = *visible;
+                }
+                ViewportCommand::CursorGrab(grab) => match grab {
+                    CursorGrab::None => self.settings.pointer_lock = false,
+                    CursorGrab::Locked => self.settings.pointer_lock = true,
+                    CursorGrab::Confined => {
+                        self.report_unsupported_command(
+                            "CursorGrab",
+                            "baseview can't confine the cursor",
+                        );
+                    }
+                },
+                ViewportCommand::CursorPosition(pos) => {
+                    if self.can_warp_cursor {
+                        self.move_pointer(*pos);
+                    } else {
+                        self.report_unsupported_command(
+                            "CursorPosition",
+                            "the cursor can only be moved on X11",
+                        );
+                    }
+                }
+                ViewportCommand::Resizable(_) => {
+                    self.report_unsupported_command(
+                        "Resizable",
+                        "whether the window can be resized is decided by baseview and the host",
+                    );
+                }
+                ViewportCommand::Visible(visible) => {
+                    if !visible {
+                        self.report_unsupported_command(
+                            "Visible",
+                            "baseview can't hide a window, close it instead",
+                        );
+                    }
+                }
+                ViewportCommand::RequestUserAttention(_) => {
+                    self.report_unsupported_command(
+                        "RequestUserAttention",
+                        "baseview can't request the user's attention",
+                    );
+                }
+                ViewportCommand::Screenshot(user_data) => {
+                    self.screenshot_requests.push(user_data.clone());
+                }
+                ViewportCommand::RequestCut => {
+                    self.egui_input.events.push(egui::Event::Cut);
+                    self.repaint_after = Some(Instant::now());
+                }
+                ViewportCommand::RequestCopy => {
+                    self.egui_input.events.push(egui::Event::Copy);
+                    self.repaint_after = Some(Instant::now());
+                }
+                ViewportCommand::RequestPaste => {
+                    self.paste_from_clipboard();
+                    self.repaint_after = Some(Instant::now());
+                }
+                ViewportCommand::Minimized(_)
+                | ViewportCommand::Maximized(_)
+                | ViewportCommand::Fullscreen(_) => {
+                    self.report_unsupported_command(
+                        command_name(command),
+                        "baseview can't change the state of a window",
+                    );
+                }
+                ViewportCommand::Decorations(_) | ViewportCommand::WindowLevel(_) => {
+                    self.report_unsupported_command(
+                        command_name(command),
+                        "the style of a window is decided when baseview opens it",
+                    );
+                }
+                ViewportCommand::Icon(_) => {
+                    self.report_unsupported_command(
+                        "Icon",
+                        "baseview can't set the icon of a window",
+                    );
+                }
+                ViewportCommand::IMEAllowed(_) => {
+                    self.report_unsupported_command(
+                        "IMEAllowed",
+                        "baseview doesn't let the window turn the input method on or off",
+                    );
+                }
+                command => {
+                    self.report_unsupported_command(
+                        command_name(command),
+                        "baseview doesn't support it",
+                    );
+                }
+            }
+        }
+
+        self.update_pointer_lock();
+        self.update_zoom();
+
+        // The window is resized when its size was changed with `Queue::resize` or by zooming.
+        if self.settings.physical_size != self.window_physical_size && requests.resize.is_none() {
+            requests.resize = Some(baseview::Size {
+                width: self.settings.physical_size.width as f64
+                    / self.native_pixels_per_point as f64,
+                height: self.settings.physical_size.height as f64
+                    / self.native_pixels_per_point as f64,
+            });
+            self.window_physical_size = self.settings.physical_size;
+        }
+
+        // Only the sizes that egui-baseview requests itself can be limited, since baseview can't
+        // stop the user or the host from resizing the window.
+        if size_limits_changed && requests.resize.is_none() {
+            let current_size = vec2(
+                self.settings.physical_size.width as f32,
+                self.settings.physical_size.height as f32,
+            ) / self.native_pixels_per_point;
+            let clamped_size = self.clamp_inner_size(current_size / self.applied_zoom_factor);
+            if clamped_size.width != current_size.x as f64
+                || clamped_size.height != current_size.y as f64
+            {
+                requests.resize = Some(clamped_size);
             }
         }
 
+        if let Some(requested) = self.repaint_signal.take() {
+            self.repaint_after = Some(self.repaint_after.map_or(requested, |t| t.min(requested)));
+        }
+
         let now = Instant::now();
         let do_repaint_now = if let Some(t) = self.repaint_after {
             now >= t || viewport_output.repaint_delay.is_zero()
         } else {
             viewport_output.repaint_delay.is_zero()
         };
+        // A screenshot needs a freshly painted frame, and texture changes that were made by
+        // another viewport's pass need to reach this window's renderer.
+        let do_repaint_now = do_repaint_now
+            || !self.screenshot_requests.is_empty()
+            || (viewports.is_some() && !full_output.textures_delta.is_empty());
 
         if do_repaint_now {
-            self.renderer.render(
-                #[cfg(feature = "opengl")]
-                window,
-                self.bg_color,
-                self.physical_size,
-                self.pixels_per_point,
-                &mut self.egui_ctx,
-                &mut full_output,
-            );
-
             self.repaint_after = None;
         } else if let Some(repaint_after) = now.checked_add(viewport_output.repaint_delay) {
-            // Schedule to repaint after the requested time has elapsed.
-            self.repaint_after = Some(repaint_after);
+            // Schedule to repaint after the requested time has elapsed, unless a repaint was
+            // already scheduled earlier.
+            self.repaint_after = Some(
+                self.repaint_after
+                    .map_or(repaint_after, |t| t.min(repaint_after)),
+            );
         }
 
-        for command in full_output.platform_output.commands {
-            match command {
-                egui::OutputCommand::CopyText(text) => {
-                    if let Some(clipboard_ctx) = &mut self.clipboard_ctx {
-                        if let Err(err) = clipboard_ctx.set_contents(text) {
-                            error!("Copy/Cut error: {}", err);
-                        }
-                    }
-                }
-                egui::OutputCommand::CopyImage(_) => {
-                    warn!("Copying images is not supported in egui_baseview.");
-                }
-                egui::OutputCommand::OpenUrl(open_url) => {
-                    if let Err(err) = open::that_detached(&open_url.url) {
-                        error!("Open error: {}", err);
-                    }
-                }
-            }
-        }
+        let do_repaint_now = do_repaint_now && self.frame_changed(&full_output);
 
-        let cursor_icon =
-            crate::translate::translate_cursor_icon(full_output.platform_output.cursor_icon);
+        let cursor_icon = if self.cursor_visible && self.pointer_lock.is_none() {
+            crate::translate::translate_cursor_icon(full_output.platform_output.cursor_icon)
+        } else {
+            MouseCursor::Hidden
+        };
         if self.current_cursor_icon != cursor_icon {
             self.current_cursor_icon = cursor_icon;
+            requests.mouse_cursor = Some(cursor_icon);
+        }
 
-            // TODO: Set mouse cursor for MacOS once baseview supports it.
-            #[cfg(not(target_os = "macos"))]
-            window.set_mouse_cursor(cursor_icon);
+        // `platform_output.ime` asks for the IME candidate window to be placed next to the text
+        // cursor. baseview doesn't give the window access to the platform's input method, so the
+        // candidate window can't be moved.
+        if full_output.platform_output.ime.is_some() && !self.reported_ime_placement {
+            self.reported_ime_placement = true;
+            debug!("The IME candidate window can't be placed next to the text cursor");
         }
 
         // A temporary workaround for keyboard input not working sometimes.
@@ -438,16 +892,410 @@ where
                 if !full_output.platform_output.events.is_empty()
                     || full_output.platform_output.ime.is_some()
                 {
-                    window.focus();
+                    requests.focus = true;
+                }
+            }
+        }
+
+        Some(FrameOutput {
+            full_output,
+            repaint: do_repaint_now,
+        })
+    }
+
+    /// Check whether a frame that is due for a repaint would look any different from the last
+    /// painted one.
+    ///
+    /// Skipped frames are counted in the [`FrameStats`] right away. A changed frame only counts as
+    /// painted once [`WindowCore::frame_presented`] is called for it.
+    fn frame_changed(&mut self, full_output: &FullOutput) -> bool {
+        let unchanged = full_output.textures_delta.is_empty()
+            && self.screenshot_requests.is_empty()
+            && self.last_painted_frame.as_ref().is_some_and(|last| {
+                last.physical_size == self.settings.physical_size
+                    && last.pixels_per_point == full_output.pixels_per_point
+                    && last.bg_color == self.settings.bg_color
+                    && last.background_generation == self.settings.background.generation()
+                    && last.shapes == full_output.shapes
+            });
+
+        if unchanged {
+            self.frame_stats.skipped_frames += 1;
+            return false;
+        }
+
+        self.unpresented_frame = Some(PaintedFrame {
+            shapes: full_output.shapes.clone(),
+            physical_size: self.settings.physical_size,
+            pixels_per_point: full_output.pixels_per_point,
+            bg_color: self.settings.bg_color,
+            background_generation: self.settings.background.generation(),
+        });
+
+        true
+    }
+
+    /// Record whether the renderer presented the frame that was last found to be changed.
+    ///
+    /// When it didn't, the window still shows an older frame, so the next frame is painted even
+    /// if it looks the same.
+    fn frame_presented(&mut self, presented: bool) {
+        let frame = self.unpresented_frame.take();
+        if presented {
+            self.frame_stats.painted_frames += 1;
+            self.last_painted_frame = frame;
+        } else {
+            self.last_painted_frame = None;
+        }
+    }
+
+    /// Apply the limits from `ViewportCommand::MinInnerSize` and `MaxInnerSize` to a size in
+    /// points.
+    fn clamp_inner_size(&self, size: Vec2) -> baseview::Size {
+        let size =
+            size.min(self.max_inner_size).max(self.min_inner_size) * self.applied_zoom_factor;
+
+        baseview::Size {
+            width: size.x.max(1.0) as f64,
+            height: size.y.max(1.0) as f64,
+        }
+    }
+
+    /// Turn a key press into text input.
+    ///
+    /// Dead keys and input method compositions are sent to egui as IME events, so that text fields
+    /// show the text that is being composed and insert the result once it is committed.
+    fn handle_text_input(&mut self, event: &keyboard_types::KeyboardEvent) {
+        use keyboard_types::Key;
+
+        match &event.key {
+            // The composed text arrives with one of the next key presses.
+            Key::Dead | Key::Process => {
+                self.start_composition();
+                if event.key == Key::Dead {
+                    self.push_ime_event(egui::ImeEvent::Preedit(String::new()));
                 }
             }
+            Key::Character(text) if is_text_input(text, event.modifiers) => {
+                if event.is_composing {
+                    self.start_composition();
+                    self.push_ime_event(egui::ImeEvent::Preedit(text.clone()));
+                } else if self.ime_composing {
+                    self.push_ime_event(egui::ImeEvent::Commit(text.clone()));
+                    self.end_composition();
+                } else {
+                    self.egui_input.events.push(egui::Event::Text(text.clone()));
+                }
+            }
+            // Any other key that is pressed while composing, like Escape, Backspace, Enter or an
+            // arrow key, cancels the composition.
+            key if self.ime_composing && !is_modifier_key(key) => self.cancel_composition(),
+            _ => {}
         }
     }
 
-    fn on_event(&mut self, _window: &mut Window, event: Event) -> EventStatus {
+    fn start_composition(&mut self) {
+        if !self.ime_composing {
+            self.ime_composing = true;
+            self.push_ime_event(egui::ImeEvent::Enabled);
+        }
+    }
+
+    fn end_composition(&mut self) {
+        if self.ime_composing {
+            self.ime_composing = false;
+            self.push_ime_event(egui::ImeEvent::Disabled);
+        }
+    }
+
+    /// End the composition without committing it, and remove the composed text from the text
+    /// field.
+    fn cancel_composition(&mut self) {
+        if self.ime_composing {
+            self.push_ime_event(egui::ImeEvent::Preedit(String::new()));
+            self.end_composition();
+        }
+    }
+
+    fn push_ime_event(&mut self, event: egui::ImeEvent) {
+        self.egui_input.events.push(egui::Event::Ime(event));
+    }
+
+    /// Pass the zoom settings from the [`Queue`] on to egui before a pass begins.
+    ///
+    /// egui only applies a new zoom factor when the next pass begins, so the factor is set here
+    /// rather than after the pass that changed it. Otherwise that pass' output would be painted at
+    /// the new scale.
+    fn sync_zoom(&mut self) {
+        // The options are shared by all viewports, so only the root window's settings are used.
+        if self.viewport_id == egui::ViewportId::ROOT
+            && self.egui_ctx.options(|options| options.zoom_with_keyboard)
+                != self.settings.zoom.enabled
+        {
+            let enabled = self.settings.zoom.enabled;
+            self.egui_ctx
+                .options_mut(|options| options.zoom_with_keyboard = enabled);
+        }
+
+        if self.settings.zoom.factor != self.applied_zoom_factor {
+            self.egui_ctx.set_zoom_factor(self.settings.zoom.factor);
+        }
+    }
+
+    /// Pick up the zoom factor that the last pass was run with, after it was set through the
+    /// [`Queue`] or by egui's keyboard shortcuts.
+    fn update_zoom(&mut self) {
+        let zoom_factor = self.egui_ctx.zoom_factor();
+        if zoom_factor == self.applied_zoom_factor {
+            return;
+        }
+
+        if self.settings.zoom.enabled {
+            // Resize the window along with the zoom, so that the layout of the UI stays the same.
+            let scale = zoom_factor / self.applied_zoom_factor;
+            self.settings.physical_size = PhySize {
+                width: (self.settings.physical_size.width as f32 * scale).round() as u32,
+                height: (self.settings.physical_size.height as f32 * scale).round() as u32,
+            };
+        }
+
+        self.settings.zoom.factor = zoom_factor;
+        self.applied_zoom_factor = zoom_factor;
+        self.pixels_per_point = self.native_pixels_per_point * zoom_factor;
+        self.points_per_pixel = self.pixels_per_point.recip();
+
+        self.repaint_after = Some(Instant::now());
+    }
+
+    /// Turn a position from baseview, which is in logical pixels, into points.
+    fn to_points(&self, position: &baseview::Point) -> Pos2 {
+        pos2(position.x as f32, position.y as f32) / self.applied_zoom_factor
+    }
+
+    /// Lock or release the pointer when that was requested during the pass.
+    fn update_pointer_lock(&mut self) {
+        if self.settings.pointer_lock == self.pointer_lock.is_some() {
+            return;
+        }
+
+        if self.settings.pointer_lock {
+            // The pointer can only be locked while it is over the window.
+            if let Some(pos) = self.pointer_pos_in_points {
+                self.pointer_lock = Some(PointerLock {
+                    origin: pos,
+                    position: pos,
+                });
+            }
+        } else if let Some(lock) = self.pointer_lock.take() {
+            let screen_rect =
+                calculate_screen_rect(self.settings.physical_size, self.points_per_pixel);
+            let pos = screen_rect.clamp(lock.position);
+            if self.can_warp_cursor {
+                self.move_pointer(pos);
+            } else {
+                self.pointer_pos_in_points = self.last_cursor_pos;
+                if let Some(pos) = self.last_cursor_pos {
+                    self.egui_input.events.push(egui::Event::PointerMoved(pos));
+                }
+            }
+            self.repaint_after = Some(Instant::now());
+        }
+    }
+
+    /// Let egui know that the pointer is gone once the last button was released outside of the
+    /// window.
+    fn release_pointer_outside(&mut self) {
+        if !self.buttons_down.is_empty() || self.pointer_lock.is_some() {
+            return;
+        }
+
+        let screen_rect = calculate_screen_rect(self.settings.physical_size, self.points_per_pixel);
+        if let Some(pos) = self.pointer_pos_in_points {
+            if !screen_rect.contains(pos) {
+                self.pointer_pos_in_points = None;
+                self.last_cursor_pos = None;
+                self.egui_input.events.push(egui::Event::PointerGone);
+            }
+        }
+    }
+
+    /// Move both the cursor and egui's pointer to `pos`.
+    fn move_pointer(&mut self, pos: Pos2) {
+        self.cursor_warp = Some(pos);
+        self.last_cursor_pos = Some(pos);
+        self.pointer_pos_in_points = Some(pos);
+        self.egui_input.events.push(egui::Event::PointerMoved(pos));
+    }
+
+    /// Take the position the window should move the cursor to.
+    fn take_cursor_warp(&mut self) -> Option<PhyPoint> {
+        self.cursor_warp.take().map(|pos| PhyPoint {
+            x: (pos.x * self.pixels_per_point).round() as i32,
+            y: (pos.y * self.pixels_per_point).round() as i32,
+        })
+    }
+
+    /// Take whether the window should grab the pointer or release its grab.
+    fn take_pointer_grab(&mut self) -> Option<bool> {
+        self.pointer_grab.take()
+    }
+
+    /// Log that a viewport command can't be honored. Each command is only reported once.
+    fn report_unsupported_command(&mut self, command: &str, reason: &str) {
+        if !self
+            .reported_commands
+            .iter()
+            .any(|reported| reported == command)
+        {
+            warn!("ViewportCommand::{command} is not supported in egui_baseview: {reason}");
+            self.reported_commands.push(command.to_owned());
+        }
+    }
+
+    /// Let egui know that the window was closed, see `egui::ViewportInfo::close_requested`.
+    fn push_close_event(&mut self) {
+        if let Some(viewport_info) = self.egui_input.viewports.get_mut(&self.viewport_id) {
+            viewport_info.events.push(egui::ViewportEvent::Close);
+        }
+    }
+
+    /// Send a captured screenshot to egui on the next frame.
+    fn deliver_screenshot(&mut self, image: egui::ColorImage) {
+        let image = Arc::new(image);
+        for user_data in self.pending_screenshots.drain(..) {
+            self.egui_input.events.push(egui::Event::Screenshot {
+                viewport_id: self.viewport_id,
+                user_data,
+                image: Arc::clone(&image),
+            });
+        }
+
+        // Schedule to repaint on the next frame so that egui receives the event.
+        self.repaint_after = Some(Instant::now());
+    }
+
+    /// Handle the clipboard and URL commands that egui sent to the platform.
+    fn handle_output_commands(&mut self, commands: Vec<egui::OutputCommand>) {
+        for command in commands {
+            match command {
+                egui::OutputCommand::OpenUrl(open_url) => {
+                    if let Err(err) = open::that_detached(&open_url.url) {
+                        error!("Open error: {}", err);
+                    }
+                }
+                command => self.copy_to_clipboard(&command),
+            }
+        }
+    }
+
+    /// Put text that was selected in a text field in the primary selection.
+    fn update_primary_selection(&mut self, platform_output: &egui::PlatformOutput) {
+        if !self.settings.primary_selection {
+            return;
+        }
+        let Some(clipboard) = &mut self.settings.clipboard else {
+            return;
+        };
+
+        for event in &platform_output.events {
+            let egui::output::OutputEvent::TextSelectionChanged(info) = event else {
+                continue;
+            };
+            let (Some(text), Some(range)) = (&info.current_text_value, &info.text_selection) else {
+                continue;
+            };
+
+            // The range goes from the cursor to where the selection started, in either direction.
+            let start = *range.start().min(range.end());
+            let end = *range.start().max(range.end());
+            if start == end {
+                continue;
+            }
+
+            let selected: String = text.chars().skip(start).take(end - start).collect();
+            // Password fields only report their masked text.
+            if selected.chars().all(|c| c == '\u{2022}') {
+                continue;
+            }
+
+            match clipboard.set_primary(selected) {
+                Ok(()) | Err(ClipboardError::Unsupported) => {}
+                Err(err) => error!("Primary selection error: {}", err),
+            }
+        }
+    }
+
+    /// Paste the primary selection into the focused text field, like a middle click does on
+    /// Linux.
+    fn paste_primary_selection(&mut self) {
+        if !self.settings.primary_selection {
+            return;
+        }
+        let Some(clipboard) = &mut self.settings.clipboard else {
+            return;
+        };
+
+        match clipboard.get_primary() {
+            Ok(text) => {
+                if !text.is_empty() {
+                    self.egui_input.events.push(egui::Event::Paste(text));
+                }
+            }
+            Err(ClipboardError::Unsupported) => {}
+            Err(err) => error!("Paste error: {}", err),
+        }
+    }
+
+    /// Paste the text on the clipboard into the focused text field.
+    fn paste_from_clipboard(&mut self) {
+        let Some(clipboard) = &mut self.settings.clipboard else {
+            return;
+        };
+
+        match clipboard.get_text() {
+            Ok(contents) => self.egui_input.events.push(egui::Event::Text(contents)),
+            Err(err) => error!("Paste error: {}", err),
+        }
+    }
+
+    /// Put the text or image of a copy command on the clipboard.
+    fn copy_to_clipboard(&mut self, command: &egui::OutputCommand) {
+        let Some(clipboard) = &mut self.settings.clipboard else {
+            return;
+        };
+
+        let result = match command {
+            egui::OutputCommand::CopyText(text) => clipboard.set_text(text.clone()),
+            egui::OutputCommand::CopyImage(image) => clipboard.set_image(image),
+            egui::OutputCommand::OpenUrl(_) => return,
+        };
+
+        if let Err(err) = result {
+            error!("Copy/Cut error: {}", err);
+        }
+    }
+
+    /// Run the user's event hook, and translate the event for egui unless the hook consumed it.
+    fn on_event(&mut self, event: &Event) -> EventStatus {
+        let action = match &mut self.settings.hooks.event {
+            Some(hook) => hook(event),
+            None => EventHookAction::Forward,
+        };
+
+        match action {
+            EventHookAction::Forward => self.translate_event(event),
+            EventHookAction::ForwardWithStatus(status) => {
+                self.translate_event(event);
+                status
+            }
+            EventHookAction::Consume(status) => status,
+        }
+    }
+
+    fn translate_event(&mut self, event: &Event) -> EventStatus {
         let mut return_status = EventStatus::Captured;
 
-        match &event {
+        match event {
             baseview::Event::Mouse(event) => match event {
                 baseview::MouseEvent::CursorMoved {
                     position,
@@ -455,9 +1303,31 @@ where
                 } => {
                     self.update_modifiers(modifiers);
 
-                    let pos = pos2(position.x as f32, position.y as f32);
-                    self.pointer_pos_in_points = Some(pos);
-                    self.egui_input.events.push(egui::Event::PointerMoved(pos));
+                    let pos = self.to_points(position);
+                    let last_pos = self.last_cursor_pos.replace(pos);
+
+                    match &mut self.pointer_lock {
+                        Some(lock) => {
+                            let delta = last_pos.map_or(Vec2::ZERO, |last_pos| pos - last_pos);
+                            if delta != Vec2::ZERO {
+                                lock.position += delta;
+                                self.egui_input.events.push(egui::Event::MouseMoved(delta));
+                            }
+
+                            // Keep the hidden cursor close to where the lock started, so that it
+                            // never gets stuck at the edge of the screen.
+                            if self.can_warp_cursor
+                                && pos.distance(lock.origin) > POINTER_LOCK_WARP_DISTANCE
+                            {
+                                self.cursor_warp = Some(lock.origin);
+                                self.last_cursor_pos = Some(lock.origin);
+                            }
+                        }
+                        None => {
+                            self.pointer_pos_in_points = Some(pos);
+                            self.egui_input.events.push(egui::Event::PointerMoved(pos));
+                        }
+                    }
                 }
                 baseview::MouseEvent::ButtonPressed { button, modifiers } => {
                     self.update_modifiers(modifiers);
@@ -472,6 +1342,17 @@ where
                             });
                         }
                     }
+
+                    if self.buttons_down.is_empty() {
+                        self.pointer_grab = Some(true);
+                    }
+                    if !self.buttons_down.contains(button) {
+                        self.buttons_down.push(*button);
+                    }
+
+                    if *button == baseview::MouseButton::Middle {
+                        self.paste_primary_selection();
+                    }
                 }
                 baseview::MouseEvent::ButtonReleased { button, modifiers } => {
                     self.update_modifiers(modifiers);
@@ -486,6 +1367,13 @@ where
                             });
                         }
                     }
+
+                    let held = !self.buttons_down.is_empty();
+                    self.buttons_down.retain(|b| b != button);
+                    if held && self.buttons_down.is_empty() {
+                        self.pointer_grab = Some(false);
+                    }
+                    self.release_pointer_outside();
                 }
                 baseview::MouseEvent::WheelScrolled {
                     delta: scroll_delta,
@@ -493,25 +1381,12 @@ where
                 } => {
                     self.update_modifiers(modifiers);
 
-                    #[allow(unused_mut)]
-                    let (unit, mut delta) = match scroll_delta {
-                        baseview::ScrollDelta::Lines { x, y } => {
-                            (egui::MouseWheelUnit::Line, egui::vec2(*x, *y))
-                        }
-
-                        baseview::ScrollDelta::Pixels { x, y } => (
-                            egui::MouseWheelUnit::Point,
-                            egui::vec2(*x, *y) * self.points_per_pixel,
-                        ),
-                    };
-
-                    if cfg!(target_os = "macos") {
-                        // This is still buggy in winit despite
-                        // https://github.com/rust-windowing/winit/issues/1695 being closed
-                        //
-                        // TODO: See if this is an issue in baseview as well.
-                        delta.x *= -1.0;
-                    }
+                    let (unit, delta) = crate::translate::translate_scroll_delta(
+                        *scroll_delta,
+                        self.egui_input.modifiers,
+                        self.points_per_pixel,
+                        &self.settings.scroll_config,
+                    );
 
                     self.egui_input.events.push(egui::Event::MouseWheel {
                         unit,
@@ -520,9 +1395,77 @@ where
                     });
                 }
                 baseview::MouseEvent::CursorLeft => {
+                    // While a button is held, the window grabs the pointer, so its movement and
+                    // the release of the button keep arriving and drags continue outside of the
+                    // window.
+                    if self.buttons_down.is_empty() {
+                        self.last_cursor_pos = None;
+
+                        // A locked pointer stays where the lock started.
+                        if self.pointer_lock.is_none() {
+                            self.pointer_pos_in_points = None;
+                            self.egui_input.events.push(egui::Event::PointerGone);
+                        }
+                    }
+                }
+                baseview::MouseEvent::DragEntered {
+                    position,
+                    modifiers,
+                    data,
+                }
+                | baseview::MouseEvent::DragMoved {
+                    position,
+                    modifiers,
+                    data,
+                } => {
+                    self.update_modifiers(modifiers);
+
+                    let pos = self.to_points(position);
+                    self.pointer_pos_in_points = Some(pos);
+                    self.egui_input.events.push(egui::Event::PointerMoved(pos));
+
+                    self.egui_input.hovered_files = dropped_paths(data)
+                        .iter()
+                        .map(|path| egui::HoveredFile {
+                            path: Some(path.clone()),
+                            ..Default::default()
+                        })
+                        .collect();
+
+                    return drop_status(data);
+                }
+                baseview::MouseEvent::DragLeft => {
+                    self.egui_input.hovered_files.clear();
                     self.pointer_pos_in_points = None;
                     self.egui_input.events.push(egui::Event::PointerGone);
                 }
+                baseview::MouseEvent::DragDropped {
+                    position,
+                    modifiers,
+                    data,
+                } => {
+                    self.update_modifiers(modifiers);
+
+                    let pos = self.to_points(position);
+                    self.pointer_pos_in_points = Some(pos);
+                    self.egui_input.events.push(egui::Event::PointerMoved(pos));
+
+                    self.egui_input.hovered_files.clear();
+                    self.egui_input
+                        .dropped_files
+                        .extend(dropped_paths(data).iter().map(|path| {
+                            egui::DroppedFile {
+                                path: Some(path.clone()),
+                                name: path
+                                    .file_name()
+                                    .map(|name| name.to_string_lossy().into_owned())
+                                    .unwrap_or_default(),
+                                ..Default::default()
+                            }
+                        }));
+
+                    return drop_status(data);
+                }
                 _ => {}
             },
             baseview::Event::Keyboard(event) => {
@@ -530,32 +1473,24 @@ where
 
                 let pressed = event.state == keyboard_types::KeyState::Down;
 
-                match event.code {
-                    Code::ShiftLeft | Code::ShiftRight => self.egui_input.modifiers.shift = pressed,
-                    Code::ControlLeft | Code::ControlRight => {
-                        self.egui_input.modifiers.ctrl = pressed;
-
-                        #[cfg(not(target_os = "macos"))]
-                        {
-                            self.egui_input.modifiers.command = pressed;
-                        }
-                    }
-                    Code::AltLeft | Code::AltRight => self.egui_input.modifiers.alt = pressed,
-                    Code::MetaLeft | Code::MetaRight => {
-                        #[cfg(target_os = "macos")]
-                        {
-                            self.egui_input.modifiers.mac_cmd = pressed;
-                            self.egui_input.modifiers.command = pressed;
-                        }
-                        // prevent `rustfmt` from breaking this
-                    }
-                    _ => (),
-                }
-
-                if let Some(key) = crate::translate::translate_virtual_key(&event.key) {
+                // Platforms differ in whether a modifier key's own event already includes it, so
+                // the key itself is applied on top of the reported modifiers.
+                let mut modifiers = event.modifiers;
+                let key_modifier = match event.code {
+                    Code::ShiftLeft | Code::ShiftRight => Modifiers::SHIFT,
+                    Code::ControlLeft | Code::ControlRight => Modifiers::CONTROL,
+                    Code::AltLeft | Code::AltRight => Modifiers::ALT,
+                    Code::MetaLeft | Code::MetaRight => Modifiers::META,
+                    _ => Modifiers::empty(),
+                };
+                modifiers.set(key_modifier, pressed);
+                self.update_modifiers(&modifiers);
+
+                let physical_key = crate::translate::translate_physical_key(event.code);
+                if let Some(key) = crate::translate::translate_key(&event.key, event.code) {
                     self.egui_input.events.push(egui::Event::Key {
                         key,
-                        physical_key: None,
+                        physical_key,
                         pressed,
                         repeat: event.repeat,
                         modifiers: self.egui_input.modifiers,
@@ -572,52 +1507,32 @@ where
                     } else if is_copy_command(self.egui_input.modifiers, event.code) {
                         self.egui_input.events.push(egui::Event::Copy);
                     } else if is_paste_command(self.egui_input.modifiers, event.code) {
-                        if let Some(clipboard_ctx) = &mut self.clipboard_ctx {
-                            match clipboard_ctx.get_contents() {
-                                Ok(contents) => {
-                                    self.egui_input.events.push(egui::Event::Text(contents))
-                                }
-                                Err(err) => {
-                                    error!("Paste error: {}", err);
-                                }
-                            }
-                        }
-                    } else if let keyboard_types::Key::Character(written) = &event.key {
-                        if !self.egui_input.modifiers.ctrl && !self.egui_input.modifiers.command {
-                            self.egui_input
-                                .events
-                                .push(egui::Event::Text(written.clone()));
-                        }
+                        self.paste_from_clipboard();
+                    } else {
+                        self.handle_text_input(event);
                     }
                 }
 
-                match &self.key_capture {
-                    KeyCapture::CaptureAll => {}
-                    KeyCapture::IgnoreAll => return_status = EventStatus::Ignored,
-                    KeyCapture::CaptureKeys(keys) => {
-                        if !keys.contains(&event.key) {
-                            return_status = EventStatus::Ignored
-                        }
-                    }
-                    KeyCapture::IgnoreKeys(keys) => {
-                        if keys.contains(&event.key) {
-                            return_status = EventStatus::Ignored
-                        }
-                    }
+                if self.settings.key_capture.status(event, &self.egui_ctx)
+                    == KeyCaptureStatus::Ignored
+                {
+                    return_status = EventStatus::Ignored;
                 }
             }
             baseview::Event::Window(event) => match event {
                 baseview::WindowEvent::Resized(window_info) => {
-                    self.pixels_per_point = match self.scale_policy {
+                    self.native_pixels_per_point = match self.scale_policy {
                         WindowScalePolicy::ScaleFactor(scale) => scale,
                         WindowScalePolicy::SystemScaleFactor => window_info.scale(),
                     } as f32;
+                    self.pixels_per_point = self.native_pixels_per_point * self.applied_zoom_factor;
                     self.points_per_pixel = self.pixels_per_point.recip();
 
-                    self.physical_size = window_info.physical_size();
+                    self.settings.physical_size = window_info.physical_size();
+                    self.window_physical_size = self.settings.physical_size;
 
                     let screen_rect =
-                        calculate_screen_rect(self.physical_size, self.points_per_pixel);
+                        calculate_screen_rect(self.settings.physical_size, self.points_per_pixel);
 
                     self.egui_input.screen_rect = Some(screen_rect);
 
@@ -626,7 +1541,7 @@ where
                         .viewports
                         .get_mut(&self.viewport_id)
                         .unwrap();
-                    viewport_info.native_pixels_per_point = Some(self.pixels_per_point);
+                    viewport_info.native_pixels_per_point = Some(self.native_pixels_per_point);
                     viewport_info.inner_rect = Some(screen_rect);
 
                     // Schedule to repaint on the next frame.
@@ -643,6 +1558,17 @@ where
                         .focused = Some(true);
                 }
                 baseview::WindowEvent::Unfocused => {
+                    // The key-up events of modifiers that are released while unfocused never
+                    // arrive.
+                    self.egui_input.modifiers = egui::Modifiers::NONE;
+                    self.cancel_composition();
+                    // The release of a button that is held while the window loses focus may never
+                    // arrive.
+                    if !self.buttons_down.is_empty() {
+                        self.buttons_down.clear();
+                        self.pointer_grab = Some(false);
+                    }
+                    self.release_pointer_outside();
                     self.egui_input
                         .events
                         .push(egui::Event::WindowFocused(false));
@@ -656,16 +1582,8 @@ where
             },
         }
 
-        // For keyboard events, also check if egui actually wants keyboard input
-        // This allows DAW shortcuts (spacebar, etc.) to pass through when no text field is focused
-        match &event {
-            baseview::Event::Keyboard(_) => {
-                if return_status == EventStatus::Captured && !self.egui_ctx.wants_keyboard_input() {
-                    EventStatus::Ignored
-                } else {
-                    return_status
-                }
-            }
+        match event {
+            baseview::Event::Keyboard(_) => return_status,
             baseview::Event::Mouse(_) => {
                 if self.egui_ctx.is_using_pointer() || self.egui_ctx.wants_pointer_input() {
                     EventStatus::Captured
@@ -678,6 +1596,531 @@ where
     }
 }
 
+/// Handles an egui-baseview application
+///
+/// Viewports that are shown with `egui::Context::show_viewport_deferred` are opened as separate
+/// windows that share the same `egui::Context`, except on macOS where they are embedded. Immediate
+/// viewports are always embedded in their parent.
+pub struct EguiWindow<State, U>
+where
+    State: 'static + Send,
+    U: FnMut(&egui::Context, &mut Queue, &mut State),
+    U: 'static + Send,
+{
+    core: WindowCore<State, U>,
+    renderer: Box<dyn DynRenderer>,
+    /// Used to open the windows of egui's deferred viewports.
+    graphics_config: GraphicsConfig,
+    pointer: PointerControl,
+}
+
+/// The update function of a deferred viewport's window, which draws the viewport's UI.
+type ViewportUpdate = Box<dyn FnMut(&egui::Context, &mut Queue, &mut ()) + Send>;
+
+impl<State, U> EguiWindow<State, U>
+where
+    State: 'static + Send,
+    U: FnMut(&egui::Context, &mut Queue, &mut State),
+    U: 'static + Send,
+{
+    /// Create the window handler.
+    ///
+    /// If none of the backends could initialize, nothing is painted and the error is returned
+    /// alongside the handler so that the caller can decide what to do with the window.
+    fn new<B>(
+        window: &mut baseview::Window<'_>,
+        open_settings: OpenSettings,
+        graphics_config: GraphicsConfig,
+        build: B,
+        update: U,
+        state: State,
+    ) -> (EguiWindow<State, U>, Option<RendererError>)
+    where
+        B: FnMut(&egui::Context, &mut Queue, &mut State),
+        B: 'static + Send,
+    {
+        let (mut egui_window, renderer_error) = Self::with_context(
+            window,
+            open_settings,
+            graphics_config,
+            egui::Context::default(),
+            build,
+            update,
+            state,
+        );
+
+        // baseview can only open top-level windows on the main thread on macOS, so deferred
+        // viewports stay embedded there.
+        #[cfg(not(target_os = "macos"))]
+        if egui_window.core.settings.viewport_windows {
+            egui_window.core.egui_ctx.set_embed_viewports(false);
+            egui_window
+                .core
+                .attach_viewports(Viewports::new(), egui::ViewportId::ROOT);
+        }
+
+        egui_window.core.install_repaint_callback();
+
+        (egui_window, renderer_error)
+    }
+
+    fn with_context<B>(
+        window: &mut baseview::Window<'_>,
+        open_settings: OpenSettings,
+        graphics_config: GraphicsConfig,
+        egui_ctx: egui::Context,
+        build: B,
+        update: U,
+        state: State,
+    ) -> (EguiWindow<State, U>, Option<RendererError>)
+    where
+        B: FnMut(&egui::Context, &mut Queue, &mut State),
+        B: 'static + Send,
+    {
+        let (renderer, renderer_error) = match graphics_config.init(window) {
+            Ok(renderer) => (renderer, None),
+            Err(err) => {
+                error!("{err} \n Nothing will be painted in this window.");
+                (Box::new(NullRenderer) as Box<dyn DynRenderer>, Some(err))
+            }
+        };
+
+        let mut core = WindowCore::new(
+            open_settings,
+            egui_ctx,
+            renderer.max_texture_side(),
+            true,
+            build,
+            update,
+            state,
+        );
+
+        let pointer = PointerControl::new(window);
+        core.can_warp_cursor = pointer.can_warp();
+
+        (
+            Self {
+                core,
+                renderer,
+                graphics_config,
+                pointer,
+            },
+            renderer_error,
+        )
+    }
+
+    /// Open a window for a deferred viewport that egui started showing.
+    fn open_viewport_window(&self, viewports: &Arc<Viewports>, new_viewport: NewViewport) {
+        let NewViewport { id, builder } = new_viewport;
+
+        let size = builder.inner_size.unwrap_or(vec2(400.0, 300.0));
+        let scale = match &self.core.scale_policy {
+            WindowScalePolicy::SystemScaleFactor => WindowScalePolicy::SystemScaleFactor,
+            WindowScalePolicy::ScaleFactor(scale) => WindowScalePolicy::ScaleFactor(*scale),
+        };
+        let settings = WindowOpenOptions {
+            title: builder.title.unwrap_or_else(|| String::from("egui")),
+            size: baseview::Size {
+                width: size.x as f64,
+                height: size.y as f64,
+            },
+            scale,
+            #[cfg(feature = "opengl")]
+            gl_config: self.graphics_config.uses_opengl().then(Default::default),
+        };
+        let open_settings = OpenSettings::new(&settings);
+
+        let viewports = Arc::clone(viewports);
+        let egui_ctx = self.core.egui_ctx.clone();
+        let graphics_config = self.graphics_config.clone();
+
+        // baseview can't open a top-level window without blocking, so every viewport gets its
+        // own thread.
+        std::thread::spawn(move || {
+            Window::open_blocking(
+                settings,
+                move |window: &mut baseview::Window<'_>| -> EguiWindow<(), ViewportUpdate> {
+                    let update: ViewportUpdate = Box::new({
+                        let viewports = Arc::clone(&viewports);
+                        move |egui_ctx, _queue, _state| {
+                            if let Some(ui_cb) = viewports.ui_cb(id) {
+                                ui_cb(egui_ctx);
+                            }
+                        }
+                    });
+
+                    let (mut egui_window, _) = EguiWindow::with_context(
+                        window,
+                        open_settings,
+                        graphics_config,
+                        egui_ctx,
+                        |_, _, _| {},
+                        update,
+                        (),
+                    );
+                    egui_window.core.attach_viewports(viewports, id);
+
+                    egui_window
+                },
+            )
+        });
+    }
+
+    /// Open a new child window.
+    ///
+    /// * `parent` - The parent window.
+    /// * `settings` - The settings of the window.
+    /// * `state` - The initial state of your application.
+    /// * `build` - Called once before the first frame. Allows you to do setup code and to
+    /// call `ctx.set_fonts()`. Optional.
+    /// * `update` - Called before each frame. Here you should update the state of your
+    /// application and build the UI.
+    ///
+    /// If none of the backends in `graphics_config` could initialize, the error is logged and the
+    /// window stays open without painting anything. Use [`EguiWindow::try_open_parented`] to
+    /// handle the error instead.
+    pub fn open_parented<P, B>(
+        parent: &P,
+        #[allow(unused_mut)] mut settings: WindowOpenOptions,
+        graphics_config: GraphicsConfig,
+        state: State,
+        build: B,
+        update: U,
+    ) -> WindowHandle
+    where
+        P: HasRawWindowHandle,
+        B: FnMut(&egui::Context, &mut Queue, &mut State),
+        B: 'static + Send,
+    {
+        #[cfg(feature = "opengl")]
+        if settings.gl_config.is_none() && graphics_config.uses_opengl() {
+            settings.gl_config = Some(Default::default());
+        }
+
+        let open_settings = OpenSettings::new(&settings);
+
+        Window::open_parented(
+            parent,
+            settings,
+            move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
+                EguiWindow::new(window, open_settings, graphics_config, build, update, state).0
+            },
+        )
+    }
+
+    /// Open a new window that blocks the current thread until the window is destroyed.
+    ///
+    /// * `settings` - The settings of the window.
+    /// * `state` - The initial state of your application.
+    /// * `build` - Called once before the first frame. Allows you to do setup code and to
+    /// call `ctx.set_fonts()`. Optional.
+    /// * `update` - Called before each frame. Here you should update the state of your
+    /// application and build the UI.
+    ///
+    /// If none of the backends in `graphics_config` could initialize, the error is logged and the
+    /// window stays open without painting anything. Use [`EguiWindow::try_open_blocking`] to
+    /// handle the error instead.
+    pub fn open_blocking<B>(
+        #[allow(unused_mut)] mut settings: WindowOpenOptions,
+        graphics_config: GraphicsConfig,
+        state: State,
+        build: B,
+        update: U,
+    ) where
+        B: FnMut(&egui::Context, &mut Queue, &mut State),
+        B: 'static + Send,
+    {
+        #[cfg(feature = "opengl")]
+        if settings.gl_config.is_none() && graphics_config.uses_opengl() {
+            settings.gl_config = Some(Default::default());
+        }
+
+        let open_settings = OpenSettings::new(&settings);
+
+        Window::open_blocking(
+            settings,
+            move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
+                EguiWindow::new(window, open_settings, graphics_config, build, update, state).0
+            },
+        )
+    }
+
+    /// Open a new child window, or return an error if none of the rendering backends could
+    /// initialize.
+    ///
+    /// The arguments are the same as in [`EguiWindow::open_parented`]. If initialization fails,
+    /// the window is closed again before this returns.
+    pub fn try_open_parented<P, B>(
+        parent: &P,
+        #[allow(unused_mut)] mut settings: WindowOpenOptions,
+        graphics_config: GraphicsConfig,
+        state: State,
+        build: B,
+        update: U,
+    ) -> Result<WindowHandle, RendererError>
+    where
+        P: HasRawWindowHandle,
+        B: FnMut(&egui::Context, &mut Queue, &mut State),
+        B: 'static + Send,
+    {
+        #[cfg(feature = "opengl")]
+        if settings.gl_config.is_none() && graphics_config.uses_opengl() {
+            settings.gl_config = Some(Default::default());
+        }
+
+        let open_settings = OpenSettings::new(&settings);
+        let renderer_error = Arc::new(Mutex::new(None));
+
+        let mut window_handle = Window::open_parented(parent, settings, {
+            let renderer_error = Arc::clone(&renderer_error);
+            move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
+                let (mut egui_window, err) =
+                    EguiWindow::new(window, open_settings, graphics_config, build, update, state);
+                store_renderer_error(&mut egui_window, &renderer_error, err);
+                egui_window
+            }
+        });
+
+        // baseview builds the window handler before `open_parented` returns on all platforms.
+        match take_renderer_error(&renderer_error) {
+            Some(err) => {
+                window_handle.close();
+                Err(err)
+            }
+            None => Ok(window_handle),
+        }
+    }
+
+    /// Open a new window that blocks the current thread until the window is destroyed, or return
+    /// an error if none of the rendering backends could initialize.
+    ///
+    /// The arguments are the same as in [`EguiWindow::open_blocking`]. If initialization fails,
+    /// the window is closed on its first frame and the error is returned once it is destroyed.
+    pub fn try_open_blocking<B>(
+        #[allow(unused_mut)] mut settings: WindowOpenOptions,
+        graphics_config: GraphicsConfig,
+        state: State,
+        build: B,
+        update: U,
+    ) -> Result<(), RendererError>
+    where
+        B: FnMut(&egui::Context, &mut Queue, &mut State),
+        B: 'static + Send,
+    {
+        #[cfg(feature = "opengl")]
+        if settings.gl_config.is_none() && graphics_config.uses_opengl() {
+            settings.gl_config = Some(Default::default());
+        }
+
+        let open_settings = OpenSettings::new(&settings);
+        let renderer_error = Arc::new(Mutex::new(None));
+
+        Window::open_blocking(settings, {
+            let renderer_error = Arc::clone(&renderer_error);
+            move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
+                let (mut egui_window, err) =
+                    EguiWindow::new(window, open_settings, graphics_config, build, update, state);
+                store_renderer_error(&mut egui_window, &renderer_error, err);
+                egui_window
+            }
+        });
+
+        match take_renderer_error(&renderer_error) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Close a window whose renderer failed to initialize on its first frame, and hand the error over
+/// to the thread that opened it.
+fn store_renderer_error<State, U>(
+    egui_window: &mut EguiWindow<State, U>,
+    renderer_error: &Mutex<Option<RendererError>>,
+    err: Option<RendererError>,
+) where
+    State: 'static + Send,
+    U: FnMut(&egui::Context, &mut Queue, &mut State),
+    U: 'static + Send,
+{
+    if let Some(err) = err {
+        egui_window.core.settings.close_requested = true;
+        if let Ok(mut renderer_error) = renderer_error.lock() {
+            *renderer_error = Some(err);
+        }
+    }
+}
+
+fn take_renderer_error(renderer_error: &Mutex<Option<RendererError>>) -> Option<RendererError> {
+    renderer_error.lock().ok().and_then(|mut err| err.take())
+}
+
+impl<State, U> WindowHandler for EguiWindow<State, U>
+where
+    State: 'static + Send,
+    U: FnMut(&egui::Context, &mut Queue, &mut State),
+    U: 'static + Send,
+{
+    fn on_frame(&mut self, window: &mut Window) {
+        let mut requests = WindowRequests::default();
+
+        if let Some(mut frame) = self.core.run_frame(&mut requests) {
+            if let Some(pos) = self.core.take_cursor_warp() {
+                self.pointer.warp(pos);
+            }
+
+            if let Some(viewports) = self.core.viewports.clone() {
+                if self.core.viewport_id == egui::ViewportId::ROOT {
+                    for new_viewport in viewports.sync_windows(&frame.full_output) {
+                        self.open_viewport_window(&viewports, new_viewport);
+                    }
+                } else if viewports.close_requested(self.core.viewport_id) {
+                    requests.close = true;
+                }
+            }
+
+            if frame.repaint {
+                if !self.core.screenshot_requests.is_empty() {
+                    self.renderer.request_screenshot();
+                    self.core
+                        .pending_screenshots
+                        .append(&mut self.core.screenshot_requests);
+                }
+
+                let presented = self.renderer.render(
+                    window,
+                    &self.core.settings.frame_background(),
+                    self.core.settings.physical_size,
+                    // The scale the pass was laid out at.
+                    frame.full_output.pixels_per_point,
+                    &self.core.egui_ctx,
+                    &mut frame.full_output,
+                );
+                self.core.frame_presented(presented);
+            }
+
+            // Some renderers only finish reading back the frame after a while.
+            if !self.core.pending_screenshots.is_empty() {
+                if let Some(image) = self.renderer.take_screenshot() {
+                    self.core.deliver_screenshot(image);
+                }
+            }
+
+            self.core
+                .update_primary_selection(&frame.full_output.platform_output);
+            self.core
+                .handle_output_commands(frame.full_output.platform_output.commands);
+        }
+
+        requests.apply(window);
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: Event) -> EventStatus {
+        if let Event::Window(baseview::WindowEvent::WillClose) = &event {
+            if let Some(viewports) = self.core.viewports.clone() {
+                let viewport_id = self.core.viewport_id;
+
+                // When the user closes the window of a deferred viewport, egui needs a pass for
+                // the viewport to let the app know, so that it stops showing the viewport.
+                if viewport_id != egui::ViewportId::ROOT && !viewports.close_requested(viewport_id)
+                {
+                    self.core.push_close_event();
+                    self.core.run_frame(&mut WindowRequests::default());
+                }
+
+                viewports.window_closed(viewport_id);
+            }
+
+            self.renderer.destroy(window);
+        }
+
+        let status = self.core.on_event(&event);
+
+        if let Some(grabbed) = self.core.take_pointer_grab() {
+            self.pointer.set_grabbed(grabbed);
+        }
+        if let Some(pos) = self.core.take_cursor_warp() {
+            self.pointer.warp(pos);
+        }
+
+        status
+    }
+}
+
+/// The name of a viewport command, without its arguments.
+fn command_name(command: &ViewportCommand) -> &str {
+    match command {
+        ViewportCommand::Minimized(_) => "Minimized",
+        ViewportCommand::Maximized(_) => "Maximized",
+        ViewportCommand::Fullscreen(_) => "Fullscreen",
+        ViewportCommand::Decorations(_) => "Decorations",
+        ViewportCommand::WindowLevel(_) => "WindowLevel",
+        ViewportCommand::CancelClose => "CancelClose",
+        ViewportCommand::Transparent(_) => "Transparent",
+        ViewportCommand::StartDrag => "StartDrag",
+        ViewportCommand::OuterPosition(_) => "OuterPosition",
+        ViewportCommand::ResizeIncrements(_) => "ResizeIncrements",
+        ViewportCommand::BeginResize(_) => "BeginResize",
+        ViewportCommand::EnableButtons { .. } => "EnableButtons",
+        ViewportCommand::IMERect(_) => "IMERect",
+        ViewportCommand::IMEPurpose(_) => "IMEPurpose",
+        ViewportCommand::SetTheme(_) => "SetTheme",
+        ViewportCommand::ContentProtected(_) => "ContentProtected",
+        ViewportCommand::MousePassthrough(_) => "MousePassthrough",
+        _ => "Other",
+    }
+}
+
+/// The files that are dragged onto the window.
+fn dropped_paths(data: &baseview::DropData) -> &[std::path::PathBuf] {
+    match data {
+        baseview::DropData::Files(paths) => paths,
+        _ => &[],
+    }
+}
+
+/// Accept drops of files, and reject everything else.
+fn drop_status(data: &baseview::DropData) -> EventStatus {
+    if dropped_paths(data).is_empty() {
+        EventStatus::Ignored
+    } else {
+        EventStatus::AcceptDrop(baseview::DropEffect::Copy)
+    }
+}
+
+/// Whether the key only changes what the other keys do.
+fn is_modifier_key(key: &keyboard_types::Key) -> bool {
+    use keyboard_types::Key;
+
+    matches!(
+        key,
+        Key::Alt
+            | Key::AltGraph
+            | Key::CapsLock
+            | Key::Control
+            | Key::Fn
+            | Key::FnLock
+            | Key::Meta
+            | Key::NumLock
+            | Key::ScrollLock
+            | Key::Shift
+            | Key::Symbol
+            | Key::SymbolLock
+            | Key::Hyper
+            | Key::Super
+    )
+}
+
+/// Whether a key press with `text` should be typed, rather than being a shortcut.
+fn is_text_input(text: &str, modifiers: Modifiers) -> bool {
+    // Windows reports AltGr as Ctrl+Alt.
+    let alt_gr = modifiers.contains(Modifiers::ALT_GRAPH)
+        || (cfg!(target_os = "windows") && modifiers.contains(Modifiers::CONTROL | Modifiers::ALT));
+    let shortcut = modifiers.intersects(Modifiers::CONTROL | Modifiers::META);
+
+    (alt_gr || !shortcut) && !text.is_empty() && !text.chars().any(char::is_control)
+}
+
 fn is_cut_command(modifiers: egui::Modifiers, keycode: keyboard_types::Code) -> bool {
     (modifiers.command && keycode == keyboard_types::Code::KeyX)
         || (cfg!(target_os = "windows")
@@ -0,0 +1,409 @@
+use std::sync::Arc;
+
+use egui::{
+    epaint::ClippedShape, pos2, vec2, ClippedPrimitive, Color32, ColorImage, Mesh, Rect, Shape,
+    TextureHandle, TextureId, TextureOptions, Vec2,
+};
+
+/// The number of segments the circle of a [`Background::RadialGradient`] is made of.
+const RADIAL_GRADIENT_SEGMENTS: u32 = 64;
+
+/// What is drawn behind the UI, on top of the background color.
+///
+/// See [`Queue::set_background`](crate::Queue::set_background).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Background {
+    /// An image, like the background bitmap of a plugin skin.
+    ///
+    /// One pixel of the image covers one point, unless `fit` scales it.
+    Image {
+        image: Arc<ColorImage>,
+        fit: BackgroundFit,
+    },
+    /// A gradient from `start` to `end` that covers the whole window.
+    ///
+    /// `angle` is the direction of the gradient in radians. At `0.0` it goes from left to right,
+    /// and it turns clockwise from there, so `std::f32::consts::FRAC_PI_2` goes from top to
+    /// bottom.
+    LinearGradient {
+        start: Color32,
+        end: Color32,
+        angle: f32,
+    },
+    /// A gradient from `center` in the middle of the window to `edge` in its corners.
+    RadialGradient { center: Color32, edge: Color32 },
+}
+
+impl Background {
+    /// An image that is scaled with `fit`.
+    pub fn image(image: impl Into<Arc<ColorImage>>, fit: BackgroundFit) -> Self {
+        Self::Image {
+            image: image.into(),
+            fit,
+        }
+    }
+}
+
+/// How a [`Background::Image`] is fitted into the window.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundFit {
+    /// Stretch the image to the size of the window.
+    #[default]
+    Stretch,
+    /// Scale the image to fit inside the window, keeping its aspect ratio. The rest of the window
+    /// shows the background color.
+    Contain,
+    /// Scale the image to cover the whole window, keeping its aspect ratio. The parts that don't
+    /// fit are cut off.
+    Cover,
+    /// Don't scale the image, and put it in the middle of the window.
+    Center,
+    /// Repeat the image from the top left corner of the window.
+    Tile,
+}
+
+impl Background {
+    /// Whether `self` and `other` draw the same background. Images are only compared by
+    /// reference, since comparing their pixels every time the background is set would be slow.
+    fn is_same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::Image { image, fit },
+                Self::Image {
+                    image: other_image,
+                    fit: other_fit,
+                },
+            ) => Arc::ptr_eq(image, other_image) && fit == other_fit,
+            _ => self == other,
+        }
+    }
+}
+
+/// The [`Background`] of a window, together with the texture of its image and its tessellated
+/// primitives.
+#[derive(Default)]
+pub(crate) struct BackgroundLayer {
+    background: Option<Background>,
+    /// The image of a [`Background::Image`], once it was uploaded.
+    texture: Option<TextureHandle>,
+    primitives: Vec<ClippedPrimitive>,
+    /// The screen rect and scale that `primitives` were tessellated for.
+    tessellated_for: Option<(Rect, f32)>,
+    /// Counts how often `primitives` changed, so that a frame with a new background is painted.
+    generation: u64,
+}
+
+impl BackgroundLayer {
+    pub fn set(&mut self, background: Option<Background>) {
+        let same = match (&self.background, &background) {
+            (Some(current), Some(background)) => current.is_same(background),
+            (None, None) => true,
+            _ => false,
+        };
+
+        if !same {
+            self.background = background;
+            self.texture = None;
+            self.tessellated_for = None;
+        }
+    }
+
+    /// Upload the image of the background, unless that was done already.
+    ///
+    /// This needs to happen during a pass, so that the texture is sent to the renderer with the
+    /// same frame that first uses it.
+    pub fn update_texture(&mut self, egui_ctx: &egui::Context) {
+        let Some(Background::Image { image, fit }) = &self.background else {
+            return;
+        };
+
+        if self.texture.is_none() {
+            let options = match fit {
+                BackgroundFit::Tile => TextureOptions::LINEAR_REPEAT,
+                _ => TextureOptions::LINEAR,
+            };
+            self.texture = Some(egui_ctx.load_texture(
+                "egui_baseview_background",
+                egui::ImageData::Color(Arc::clone(image)),
+                options,
+            ));
+        }
+    }
+
+    /// Tessellate the background for `screen_rect`, unless that was done already.
+    ///
+    /// This needs to happen after [`BackgroundLayer::update_texture`], so that the image has a
+    /// texture to refer to.
+    pub fn tessellate(
+        &mut self,
+        egui_ctx: &egui::Context,
+        screen_rect: Rect,
+        pixels_per_point: f32,
+    ) {
+        if self.tessellated_for == Some((screen_rect, pixels_per_point)) {
+            return;
+        }
+
+        let shapes = self.shape(screen_rect).into_iter().collect();
+        self.primitives = egui_ctx.tessellate(shapes, pixels_per_point);
+        self.tessellated_for = Some((screen_rect, pixels_per_point));
+        self.generation += 1;
+    }
+
+    /// The primitives from the last call to [`BackgroundLayer::tessellate`].
+    pub fn primitives(&self) -> &[ClippedPrimitive] {
+        &self.primitives
+    }
+
+    /// Changes whenever [`BackgroundLayer::primitives`] do.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The shape that draws the background into `screen_rect`.
+    fn shape(&self, screen_rect: Rect) -> Option<ClippedShape> {
+        let shape = match self.background.as_ref()? {
+            Background::Image { image, fit } => {
+                let texture = self.texture.as_ref()?;
+                image_shape(texture.id(), image_size(image)?, *fit, screen_rect)
+            }
+            Background::LinearGradient { start, end, angle } => {
+                linear_gradient_shape(*start, *end, *angle, screen_rect)
+            }
+            Background::RadialGradient { center, edge } => {
+                radial_gradient_shape(*center, *edge, screen_rect)
+            }
+        };
+
+        Some(ClippedShape {
+            clip_rect: screen_rect,
+            shape,
+        })
+    }
+}
+
+fn image_size(image: &ColorImage) -> Option<Vec2> {
+    let size = vec2(image.size[0] as f32, image.size[1] as f32);
+    (size.x > 0.0 && size.y > 0.0).then_some(size)
+}
+
+fn image_shape(
+    texture_id: TextureId,
+    image_size: Vec2,
+    fit: BackgroundFit,
+    screen_rect: Rect,
+) -> Shape {
+    let full_uv = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+    let scale = screen_rect.size() / image_size;
+
+    let (rect, uv) = match fit {
+        BackgroundFit::Stretch => (screen_rect, full_uv),
+        BackgroundFit::Contain => (
+            Rect::from_center_size(screen_rect.center(), image_size * scale.min_elem()),
+            full_uv,
+        ),
+        BackgroundFit::Cover => (
+            Rect::from_center_size(screen_rect.center(), image_size * scale.max_elem()),
+            full_uv,
+        ),
+        BackgroundFit::Center => (
+            Rect::from_center_size(screen_rect.center(), image_size),
+            full_uv,
+        ),
+        BackgroundFit::Tile => (
+            screen_rect,
+            Rect::from_min_max(pos2(0.0, 0.0), scale.to_pos2()),
+        ),
+    };
+
+    Shape::image(texture_id, rect, uv, Color32::WHITE)
+}
+
+fn linear_gradient_shape(start: Color32, end: Color32, angle: f32, screen_rect: Rect) -> Shape {
+    let direction = Vec2::angled(angle);
+    let corners = [
+        screen_rect.left_top(),
+        screen_rect.right_top(),
+        screen_rect.right_bottom(),
+        screen_rect.left_bottom(),
+    ];
+
+    // The gradient runs between the two corners that are furthest apart along its direction, so
+    // those get exactly the start and end colors.
+    let half_length = corners
+        .iter()
+        .map(|corner| (*corner - screen_rect.center()).dot(direction).abs())
+        .fold(0.0, f32::max);
+
+    let mut mesh = Mesh::default();
+    for corner in corners {
+        let t = if half_length > 0.0 {
+            ((corner - screen_rect.center()).dot(direction) / half_length + 1.0) / 2.0
+        } else {
+            0.0
+        };
+        mesh.colored_vertex(corner, lerp_color(start, end, t));
+    }
+    mesh.add_triangle(0, 1, 2);
+    mesh.add_triangle(0, 2, 3);
+
+    Shape::mesh(mesh)
+}
+
+fn radial_gradient_shape(center: Color32, edge: Color32, screen_rect: Rect) -> Shape {
+    let radius = screen_rect.size().length() / 2.0;
+
+    let mut mesh = Mesh::default();
+    mesh.colored_vertex(screen_rect.center(), center);
+    for i in 0..RADIAL_GRADIENT_SEGMENTS {
+        let angle = i as f32 / RADIAL_GRADIENT_SEGMENTS as f32 * std::f32::consts::TAU;
+        mesh.colored_vertex(screen_rect.center() + Vec2::angled(angle) * radius, edge);
+        mesh.add_triangle(0, i + 1, (i + 1) % RADIAL_GRADIENT_SEGMENTS + 1);
+    }
+
+    Shape::mesh(mesh)
+}
+
+fn lerp_color(a: Color32, b: Color32, t: f32) -> Color32 {
+    let lerp = |a: u8, b: u8| egui::lerp(a as f32..=b as f32, t).round() as u8;
+
+    Color32::from_rgba_premultiplied(
+        lerp(a.r(), b.r()),
+        lerp(a.g(), b.g()),
+        lerp(a.b(), b.b()),
+        lerp(a.a(), b.a()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn mesh(shape: &Shape) -> &Mesh {
+        let Shape::Mesh(mesh) = shape else {
+            panic!("not a mesh: {shape:?}");
+        };
+        let mesh: &Mesh = mesh;
+        mesh
+    }
+
+    /// The rectangle an image shape covers, and the part of the texture it shows.
+    fn image_rects(shape: &Shape) -> (Rect, Rect) {
+        let vertices = &mesh(shape).vertices;
+        (
+            Rect::from_points(&vertices.iter().map(|v| v.pos).collect::<Vec<_>>()),
+            Rect::from_points(&vertices.iter().map(|v| v.uv).collect::<Vec<_>>()),
+        )
+    }
+
+    fn screen_rect() -> Rect {
+        Rect::from_min_size(pos2(0.0, 0.0), vec2(200.0, 100.0))
+    }
+
+    fn full_uv() -> Rect {
+        Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0))
+    }
+
+    fn fitted(fit: BackgroundFit) -> (Rect, Rect) {
+        image_rects(&image_shape(
+            TextureId::Managed(1),
+            vec2(50.0, 50.0),
+            fit,
+            screen_rect(),
+        ))
+    }
+
+    #[test]
+    fn image_fits() {
+        assert_eq!(fitted(BackgroundFit::Stretch), (screen_rect(), full_uv()));
+        assert_eq!(
+            fitted(BackgroundFit::Contain),
+            (
+                Rect::from_min_max(pos2(50.0, 0.0), pos2(150.0, 100.0)),
+                full_uv()
+            )
+        );
+        assert_eq!(
+            fitted(BackgroundFit::Cover),
+            (
+                Rect::from_min_max(pos2(0.0, -50.0), pos2(200.0, 150.0)),
+                full_uv()
+            )
+        );
+        assert_eq!(
+            fitted(BackgroundFit::Center),
+            (
+                Rect::from_min_max(pos2(75.0, 25.0), pos2(125.0, 75.0)),
+                full_uv()
+            )
+        );
+    }
+
+    #[test]
+    fn tiled_images_repeat_once_per_image_size() {
+        assert_eq!(
+            fitted(BackgroundFit::Tile),
+            (
+                screen_rect(),
+                Rect::from_min_max(pos2(0.0, 0.0), pos2(4.0, 2.0))
+            )
+        );
+    }
+
+    fn vertex_color(mesh: &Mesh, pos: egui::Pos2) -> Color32 {
+        mesh.vertices
+            .iter()
+            .find(|vertex| vertex.pos == pos)
+            .unwrap()
+            .color
+    }
+
+    #[test]
+    fn linear_gradients_end_in_the_corners() {
+        let rect = screen_rect();
+
+        let shape = linear_gradient_shape(Color32::RED, Color32::BLUE, 0.0, rect);
+        let mesh = mesh(&shape);
+        assert_eq!(vertex_color(mesh, rect.left_top()), Color32::RED);
+        assert_eq!(vertex_color(mesh, rect.left_bottom()), Color32::RED);
+        assert_eq!(vertex_color(mesh, rect.right_top()), Color32::BLUE);
+        assert_eq!(vertex_color(mesh, rect.right_bottom()), Color32::BLUE);
+
+        let shape = linear_gradient_shape(Color32::RED, Color32::BLUE, FRAC_PI_2, rect);
+        let mesh = mesh(&shape);
+        assert_eq!(vertex_color(mesh, rect.left_top()), Color32::RED);
+        assert_eq!(vertex_color(mesh, rect.right_top()), Color32::RED);
+        assert_eq!(vertex_color(mesh, rect.left_bottom()), Color32::BLUE);
+        assert_eq!(vertex_color(mesh, rect.right_bottom()), Color32::BLUE);
+    }
+
+    #[test]
+    fn radial_gradients_go_from_the_center_to_the_edge() {
+        let shape = radial_gradient_shape(Color32::RED, Color32::BLUE, screen_rect());
+        let mesh = mesh(&shape);
+
+        assert_eq!(mesh.vertices[0].pos, screen_rect().center());
+        assert_eq!(mesh.vertices[0].color, Color32::RED);
+        assert!(mesh.vertices[1..]
+            .iter()
+            .all(|vertex| vertex.color == Color32::BLUE));
+    }
+
+    #[test]
+    fn images_are_compared_by_reference() {
+        let image = Arc::new(ColorImage::filled([2, 2], Color32::RED));
+        let background = Background::image(Arc::clone(&image), BackgroundFit::Tile);
+
+        assert!(background.is_same(&Background::image(image, BackgroundFit::Tile)));
+        assert!(!background.is_same(&Background::image(
+            ColorImage::filled([2, 2], Color32::RED),
+            BackgroundFit::Tile
+        )));
+        assert!(!background.is_same(&Background::RadialGradient {
+            center: Color32::RED,
+            edge: Color32::BLUE,
+        }));
+    }
+}
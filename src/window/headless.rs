@@ -0,0 +1,195 @@
+use baseview::{Event, EventStatus, PhySize, WindowOpenOptions};
+use egui::{FullOutput, Rgba};
+
+use super::{
+    FrameStats, KeyCapture, OpenSettings, Queue, RepaintHandle, WindowCore, WindowRequests,
+};
+use crate::FrameBackground;
+
+/// The maximum texture side that is reported to egui by a [`HeadlessWindow`].
+const MAX_TEXTURE_SIDE: usize = 2048;
+
+/// The result of stepping a [`HeadlessWindow`] by one frame.
+pub struct HeadlessFrame {
+    /// The output of the egui pass.
+    ///
+    /// Since nothing gets painted, the shapes and textures delta are left untouched. They can be
+    /// painted into an image with an [`OffscreenRenderer`](crate::OffscreenRenderer), over the
+    /// [`HeadlessWindow::background`].
+    pub full_output: FullOutput,
+    /// Whether a real window would have painted this frame.
+    ///
+    /// This is `false` when the frame is the same as the last painted one, see [`FrameStats`].
+    pub repainted: bool,
+    /// The changes to the window that were requested during this frame.
+    pub requests: WindowRequests,
+    /// The screenshots that egui requested with [`egui::ViewportCommand::Screenshot`].
+    ///
+    /// A headless window has nothing to capture, so these are not answered with an
+    /// [`egui::Event::Screenshot`]. Render [`HeadlessFrame::full_output`] with an
+    /// [`OffscreenRenderer`](crate::OffscreenRenderer) instead.
+    pub screenshot_requests: Vec<egui::UserData>,
+}
+
+/// Drives an egui-baseview application without a window or a renderer.
+///
+/// Events go through the same translation as in an [`EguiWindow`](super::EguiWindow), and
+/// [`HeadlessWindow::step`] runs a frame the same way `on_frame` does. This makes it possible to
+/// test input handling, key capture, and close/resize requests without a display.
+///
+/// The system clipboard is not used. Set a [`MemoryClipboard`](crate::MemoryClipboard) with
+/// [`Queue::set_clipboard`] to test copy and paste. egui's other platform commands (such as
+/// opening URLs) are not executed. All of them can be inspected through
+/// [`HeadlessFrame::full_output`].
+pub struct HeadlessWindow<State, U>
+where
+    State: 'static + Send,
+    U: FnMut(&egui::Context, &mut Queue, &mut State),
+    U: 'static + Send,
+{
+    core: WindowCore<State, U>,
+    pointer_grabbed: bool,
+}
+
+impl<State, U> HeadlessWindow<State, U>
+where
+    State: 'static + Send,
+    U: FnMut(&egui::Context, &mut Queue, &mut State),
+    U: 'static + Send,
+{
+    /// Create a new headless window.
+    ///
+    /// * `settings` - The settings of the window. Only the title, size and scale policy are used.
+    /// * `state` - The initial state of your application.
+    /// * `build` - Called once before the first frame. Allows you to do setup code and to
+    /// call `ctx.set_fonts()`. Optional.
+    /// * `update` - Called before each frame. Here you should update the state of your
+    /// application and build the UI.
+    pub fn new<B>(settings: &WindowOpenOptions, state: State, build: B, update: U) -> Self
+    where
+        B: FnMut(&egui::Context, &mut Queue, &mut State),
+        B: 'static + Send,
+    {
+        let open_settings = OpenSettings::new(settings);
+
+        let core = WindowCore::new(
+            open_settings,
+            egui::Context::default(),
+            MAX_TEXTURE_SIDE,
+            false,
+            build,
+            update,
+            state,
+        );
+        core.install_repaint_callback();
+
+        Self {
+            core,
+            pointer_grabbed: false,
+        }
+    }
+
+    /// Send an event to the application the same way baseview would, and return the status
+    /// that would be reported back to the host.
+    pub fn send_event(&mut self, event: Event) -> EventStatus {
+        let status = self.core.on_event(&event);
+        if let Some(grabbed) = self.core.take_pointer_grab() {
+            self.pointer_grabbed = grabbed;
+        }
+
+        status
+    }
+
+    /// Run a single frame.
+    pub fn step(&mut self) -> HeadlessFrame {
+        let mut requests = WindowRequests::default();
+
+        let frame = self.core.run_frame(&mut requests);
+        if let Some(frame) = &frame {
+            // Without a renderer, every frame that would be painted counts as presented.
+            if frame.repaint {
+                self.core.frame_presented(true);
+            }
+            self.core
+                .update_primary_selection(&frame.full_output.platform_output);
+            for command in &frame.full_output.platform_output.commands {
+                self.core.copy_to_clipboard(command);
+            }
+        }
+        let screenshot_requests = std::mem::take(&mut self.core.screenshot_requests);
+
+        match frame {
+            Some(frame) => HeadlessFrame {
+                full_output: frame.full_output,
+                repainted: frame.repaint,
+                requests,
+                screenshot_requests,
+            },
+            None => HeadlessFrame {
+                full_output: FullOutput::default(),
+                repainted: false,
+                requests,
+                screenshot_requests,
+            },
+        }
+    }
+
+    /// The egui context of the application.
+    pub fn egui_ctx(&self) -> &egui::Context {
+        &self.core.egui_ctx
+    }
+
+    /// The state of the application.
+    pub fn state(&self) -> Option<&State> {
+        self.core.user_state.as_ref()
+    }
+
+    /// The state of the application.
+    pub fn state_mut(&mut self) -> Option<&mut State> {
+        self.core.user_state.as_mut()
+    }
+
+    /// The current size of the window in physical pixels.
+    pub fn physical_size(&self) -> PhySize {
+        self.core.settings.physical_size
+    }
+
+    /// The current scale factor of the window.
+    pub fn pixels_per_point(&self) -> f32 {
+        self.core.pixels_per_point
+    }
+
+    /// The background color that would be painted behind the UI.
+    pub fn bg_color(&self) -> Rgba {
+        self.core.settings.bg_color
+    }
+
+    /// Everything that would be painted behind the UI, which is the background color and the
+    /// [`Background`](crate::Background). Pass this to an
+    /// [`OffscreenRenderer`](crate::OffscreenRenderer) to render a frame the way a window would.
+    pub fn background(&self) -> FrameBackground<'_> {
+        self.core.settings.frame_background()
+    }
+
+    /// A handle that can request a repaint from any thread, the same as
+    /// [`Queue::repaint_handle`].
+    pub fn repaint_handle(&self) -> RepaintHandle {
+        RepaintHandle::new(self.core.repaint_signal.clone())
+    }
+
+    /// Whether a real window would have grabbed the pointer, which it does while a mouse button is
+    /// held.
+    pub fn pointer_grabbed(&self) -> bool {
+        self.pointer_grabbed
+    }
+
+    /// How many frames would have been painted or skipped so far.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.core.frame_stats
+    }
+
+    /// How key events are currently captured from the host.
+    pub fn key_capture(&self) -> &KeyCapture {
+        &self.core.settings.key_capture
+    }
+}
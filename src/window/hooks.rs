@@ -0,0 +1,30 @@
+use baseview::{Event, EventStatus};
+
+/// What to do with a [`baseview::Event`] after the event hook has seen it.
+///
+/// See [`Queue::set_event_hook`](crate::Queue::set_event_hook).
+#[derive(Debug)]
+pub enum EventHookAction {
+    /// Pass the event on to egui.
+    Forward,
+    /// Pass the event on to egui, but report this status to baseview instead of the one egui
+    /// would.
+    ForwardWithStatus(EventStatus),
+    /// Don't pass the event on to egui, and report this status to baseview.
+    Consume(EventStatus),
+}
+
+type EventHook = Box<dyn FnMut(&Event) -> EventHookAction + Send>;
+type InputHook = Box<dyn FnMut(&mut egui::RawInput) + Send>;
+type OutputHook = Box<dyn FnMut(&egui::FullOutput) + Send>;
+
+/// The user's hooks into the event and pass pipeline of a window.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    /// Called before a baseview event is translated.
+    pub event: Option<EventHook>,
+    /// Called with the input before each egui pass begins.
+    pub input: Option<InputHook>,
+    /// Called with the output after each egui pass ends.
+    pub output: Option<OutputHook>,
+}
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use keyboard_types::{Key, KeyboardEvent, Modifiers};
+
+/// Whether a key event is used by the window or passed on to the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCaptureStatus {
+    /// The window uses the key event.
+    Captured,
+    /// The key event is passed on to the host.
+    Ignored,
+}
+
+/// Describes how to handle capturing key events from the host.
+///
+/// With [`CaptureAll`](Self::CaptureAll), [`CaptureKeys`](Self::CaptureKeys) and
+/// [`IgnoreKeys`](Self::IgnoreKeys), keys are only captured while egui wants keyboard input, so
+/// that host shortcuts like the spacebar keep working while no text field has focus. The shortcut
+/// modes ignore egui's keyboard focus, and [`Custom`](Self::Custom) decides on its own.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub enum KeyCapture {
+    #[default]
+    /// All keys will be captured from the host.
+    CaptureAll,
+    /// No keys will be captured from the host.
+    IgnoreAll,
+    /// Only the given keys will be captured from the host.
+    CaptureKeys(Vec<keyboard_types::Key>),
+    /// All keys except the given ones will be captured from the host.
+    IgnoreKeys(Vec<keyboard_types::Key>),
+    /// Only the given shortcuts will be captured from the host, whether or not egui wants keyboard
+    /// input.
+    CaptureShortcuts(Vec<KeyShortcut>),
+    /// All keys except the given shortcuts will be captured from the host, whether or not egui
+    /// wants keyboard input.
+    IgnoreShortcuts(Vec<KeyShortcut>),
+    /// Decide for every key event with a function, see [`KeyCapture::custom`].
+    Custom(KeyCaptureFn),
+}
+
+impl KeyCapture {
+    /// Decide for every key event whether it is captured, using the event and egui's state.
+    ///
+    /// The function is called after egui has received the event. For example, this passes all
+    /// keys on to the host unless a text field has focus:
+    ///
+    /// ```ignore
+    /// KeyCapture::custom(|_event, egui_ctx| {
+    ///     if egui_ctx.wants_keyboard_input() {
+    ///         KeyCaptureStatus::Captured
+    ///     } else {
+    ///         KeyCaptureStatus::Ignored
+    ///     }
+    /// })
+    /// ```
+    pub fn custom(
+        f: impl Fn(&KeyboardEvent, &egui::Context) -> KeyCaptureStatus + Send + Sync + 'static,
+    ) -> Self {
+        Self::Custom(KeyCaptureFn(Arc::new(f)))
+    }
+
+    /// Whether `event` is captured from the host.
+    pub fn status(&self, event: &KeyboardEvent, egui_ctx: &egui::Context) -> KeyCaptureStatus {
+        let captured = match self {
+            Self::CaptureAll => egui_ctx.wants_keyboard_input(),
+            Self::IgnoreAll => false,
+            Self::CaptureKeys(keys) => keys.contains(&event.key) && egui_ctx.wants_keyboard_input(),
+            Self::IgnoreKeys(keys) => !keys.contains(&event.key) && egui_ctx.wants_keyboard_input(),
+            Self::CaptureShortcuts(shortcuts) => {
+                shortcuts.iter().any(|shortcut| shortcut.matches(event))
+            }
+            Self::IgnoreShortcuts(shortcuts) => {
+                !shortcuts.iter().any(|shortcut| shortcut.matches(event))
+            }
+            Self::Custom(f) => return (f.0)(event, egui_ctx),
+        };
+
+        if captured {
+            KeyCaptureStatus::Captured
+        } else {
+            KeyCaptureStatus::Ignored
+        }
+    }
+}
+
+/// The function of [`KeyCapture::Custom`].
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub struct KeyCaptureFn(
+    Arc<dyn Fn(&KeyboardEvent, &egui::Context) -> KeyCaptureStatus + Send + Sync>,
+);
+
+impl std::fmt::Debug for KeyCaptureFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyCaptureFn").finish_non_exhaustive()
+    }
+}
+
+/// Two functions are only equal if they are the same function.
+impl PartialEq for KeyCaptureFn {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A key together with the modifiers that have to be held, like Ctrl+S.
+///
+/// ```ignore
+/// KeyCapture::IgnoreShortcuts(vec![
+///     KeyShortcut::new(Modifiers::CONTROL, Key::Character("s".into())),
+///     KeyShortcut::key(Key::Character(" ".into())),
+/// ])
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyShortcut {
+    /// The modifiers that have to be held, and no others. Lock keys like Caps Lock don't count.
+    pub modifiers: Modifiers,
+    /// The key that is pressed. Characters match regardless of case.
+    pub key: Key,
+}
+
+impl KeyShortcut {
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        Self { modifiers, key }
+    }
+
+    /// A key that is pressed without any modifiers.
+    pub fn key(key: Key) -> Self {
+        Self::new(Modifiers::empty(), key)
+    }
+
+    /// Whether `event` is for this key with exactly these modifiers held.
+    ///
+    /// Characters are compared case-insensitively, so Ctrl+Shift+S matches
+    /// `Character("s")` with `CONTROL | SHIFT`.
+    pub fn matches(&self, event: &KeyboardEvent) -> bool {
+        let key_matches = match (&self.key, &event.key) {
+            (Key::Character(a), Key::Character(b)) => a.to_lowercase() == b.to_lowercase(),
+            (a, b) => a == b,
+        };
+
+        key_matches && shortcut_modifiers(event.modifiers) == shortcut_modifiers(self.modifiers)
+    }
+}
+
+/// The modifiers shortcuts are compared on. Lock keys like Caps Lock are left out.
+fn shortcut_modifiers(modifiers: Modifiers) -> Modifiers {
+    modifiers & (Modifiers::SHIFT | Modifiers::CONTROL | Modifiers::ALT | Modifiers::META)
+}
@@ -0,0 +1,250 @@
+use baseview::{PhyPoint, Window};
+
+/// Controls the mouse pointer in ways that baseview doesn't support.
+///
+/// The pointer can be grabbed on X11 and Windows, and only moved on X11. macOS keeps sending the
+/// pointer's movement to the window while a button is held on its own.
+pub(crate) struct PointerControl {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    x11: Option<x11::X11Pointer>,
+    #[cfg(target_os = "windows")]
+    win32: Option<win32::Win32Pointer>,
+}
+
+impl PointerControl {
+    pub fn new(window: &Window) -> Self {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            Self {
+                x11: x11::X11Pointer::new(window),
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            Self {
+                win32: win32::Win32Pointer::new(window),
+            }
+        }
+
+        #[cfg(not(any(all(unix, not(target_os = "macos")), target_os = "windows")))]
+        {
+            let _ = window;
+            Self {}
+        }
+    }
+
+    /// Whether [`PointerControl::warp`] can move the pointer.
+    ///
+    /// This doesn't connect to the X server yet, so a failed connection is only noticed once the
+    /// pointer is moved.
+    pub fn can_warp(&self) -> bool {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            self.x11.is_some()
+        }
+
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        {
+            false
+        }
+    }
+
+    /// Move the pointer to `position`, relative to the window.
+    pub fn warp(&mut self, position: PhyPoint) {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if let Some(x11) = &mut self.x11 {
+            x11.warp(position);
+        }
+
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        let _ = position;
+    }
+
+    /// Grab the pointer, so that its movement and the release of its buttons keep being sent to the
+    /// window while it is outside of it, or release the grab again.
+    pub fn set_grabbed(&mut self, grabbed: bool) {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if let Some(x11) = &mut self.x11 {
+            x11.set_grabbed(grabbed);
+        }
+
+        #[cfg(target_os = "windows")]
+        if let Some(win32) = &mut self.win32 {
+            win32.set_grabbed(grabbed);
+        }
+
+        #[cfg(not(any(all(unix, not(target_os = "macos")), target_os = "windows")))]
+        let _ = grabbed;
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod x11 {
+    use baseview::{PhyPoint, Window};
+    use raw_window_handle::{
+        HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+    };
+    use x11::xlib;
+    use x11rb::{
+        connection::Connection, protocol::xproto::ConnectionExt, rust_connection::RustConnection,
+    };
+
+    #[cfg(feature = "nih_log")]
+    use nih_plug::log::{error, warn};
+
+    #[cfg(all(feature = "tracing", not(feature = "nih_log")))]
+    use tracing::{error, warn};
+
+    /// baseview doesn't share its connection to the X server, so this uses its own to move the
+    /// pointer.
+    pub(super) struct X11Pointer {
+        window: u32,
+        /// baseview's own Xlib display. The pointer is grabbed through it, since a grab sends the
+        /// pointer's events to the connection that grabbed it.
+        display: Option<*mut xlib::Display>,
+        /// The connection is only opened once the pointer is moved, since most windows never do.
+        conn: Option<RustConnection>,
+        /// Set when connecting failed, so that it isn't retried and logged on every move.
+        connect_failed: bool,
+    }
+
+    impl X11Pointer {
+        pub fn new(window: &Window) -> Option<Self> {
+            let window = match window.raw_window_handle() {
+                RawWindowHandle::Xlib(handle) => handle.window as u32,
+                RawWindowHandle::Xcb(handle) => handle.window,
+                _ => return None,
+            };
+            let display = match window.raw_display_handle() {
+                RawDisplayHandle::Xlib(handle) if !handle.display.is_null() => {
+                    Some(handle.display as *mut xlib::Display)
+                }
+                _ => None,
+            };
+
+            Some(Self {
+                window,
+                display,
+                conn: None,
+                connect_failed: false,
+            })
+        }
+
+        fn conn(&mut self) -> Option<&RustConnection> {
+            if self.connect_failed {
+                return None;
+            }
+
+            let conn = match self.conn.take() {
+                Some(conn) => conn,
+                None => match x11rb::connect(None) {
+                    Ok((conn, _)) => conn,
+                    Err(err) => {
+                        warn!(
+                            "Failed to connect to the X server, the pointer can't be moved: {err}"
+                        );
+                        self.connect_failed = true;
+                        return None;
+                    }
+                },
+            };
+            Some(self.conn.insert(conn))
+        }
+
+        pub fn warp(&mut self, position: PhyPoint) {
+            let window = self.window;
+            let Some(conn) = self.conn() else {
+                return;
+            };
+
+            let result = conn
+                .warp_pointer(
+                    x11rb::NONE,
+                    window,
+                    0,
+                    0,
+                    0,
+                    0,
+                    position.x as i16,
+                    position.y as i16,
+                )
+                .and_then(|_| conn.flush());
+
+            if let Err(err) = result {
+                error!("Failed to move the pointer: {}", err);
+            }
+        }
+
+        pub fn set_grabbed(&mut self, grabbed: bool) {
+            let Some(display) = self.display else {
+                return;
+            };
+
+            // SAFETY: baseview keeps the display open for as long as the window is open, and the
+            // window handler is dropped before the window closes.
+            unsafe {
+                if grabbed {
+                    let event_mask = xlib::ButtonPressMask
+                        | xlib::ButtonReleaseMask
+                        | xlib::PointerMotionMask
+                        | xlib::EnterWindowMask
+                        | xlib::LeaveWindowMask;
+                    let result = xlib::XGrabPointer(
+                        display,
+                        self.window as xlib::Window,
+                        xlib::True,
+                        event_mask as u32,
+                        xlib::GrabModeAsync,
+                        xlib::GrabModeAsync,
+                        0,
+                        0,
+                        xlib::CurrentTime,
+                    );
+                    if result != xlib::GrabSuccess {
+                        warn!("Failed to grab the pointer: {}", result);
+                    }
+                } else {
+                    xlib::XUngrabPointer(display, xlib::CurrentTime);
+                }
+                xlib::XFlush(display);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use baseview::Window;
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use winapi::{
+        shared::windef::HWND,
+        um::winuser::{ReleaseCapture, SetCapture},
+    };
+
+    pub(super) struct Win32Pointer {
+        hwnd: HWND,
+    }
+
+    impl Win32Pointer {
+        pub fn new(window: &Window) -> Option<Self> {
+            match window.raw_window_handle() {
+                RawWindowHandle::Win32(handle) if !handle.hwnd.is_null() => Some(Self {
+                    hwnd: handle.hwnd as HWND,
+                }),
+                _ => None,
+            }
+        }
+
+        pub fn set_grabbed(&mut self, grabbed: bool) {
+            // SAFETY: The window handle stays valid for as long as the window handler exists.
+            unsafe {
+                if grabbed {
+                    SetCapture(self.hwnd);
+                } else {
+                    ReleaseCapture();
+                }
+            }
+        }
+    }
+}
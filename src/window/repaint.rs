@@ -0,0 +1,77 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// No repaint was requested.
+const NO_DEADLINE: u64 = u64::MAX;
+
+/// The earliest time a window was asked to repaint from outside of `on_frame`.
+pub(crate) struct RepaintSignal {
+    start_time: Instant,
+    /// Nanoseconds since `start_time`, or [`NO_DEADLINE`].
+    deadline: AtomicU64,
+}
+
+impl RepaintSignal {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            start_time: Instant::now(),
+            deadline: AtomicU64::new(NO_DEADLINE),
+        })
+    }
+
+    pub fn request_repaint_after(&self, delay: Duration) {
+        let deadline = self.start_time.elapsed().saturating_add(delay).as_nanos();
+        let deadline = deadline.min(u128::from(NO_DEADLINE - 1)) as u64;
+
+        self.deadline.fetch_min(deadline, Ordering::Relaxed);
+    }
+
+    /// Take the requested deadline, if there is one.
+    pub fn take(&self) -> Option<Instant> {
+        match self.deadline.swap(NO_DEADLINE, Ordering::Relaxed) {
+            NO_DEADLINE => None,
+            nanos => Some(self.start_time + Duration::from_nanos(nanos)),
+        }
+    }
+}
+
+/// Wakes up the UI from any thread.
+///
+/// The handle can be cloned and sent to other threads, such as the audio thread when it has new
+/// meter data. Requesting a repaint is lock-free and doesn't allocate. The window picks it up on
+/// its next frame.
+///
+/// Get one with [`Queue::repaint_handle`](crate::Queue::repaint_handle).
+#[derive(Clone)]
+pub struct RepaintHandle {
+    signal: Arc<RepaintSignal>,
+}
+
+impl RepaintHandle {
+    pub(crate) fn new(signal: Arc<RepaintSignal>) -> Self {
+        Self { signal }
+    }
+
+    /// Repaint the window on its next frame.
+    pub fn request_repaint(&self) {
+        self.signal.request_repaint_after(Duration::ZERO);
+    }
+
+    /// Repaint the window once `delay` has elapsed.
+    ///
+    /// If this is called multiple times, the earliest deadline is used.
+    pub fn request_repaint_after(&self, delay: Duration) {
+        self.signal.request_repaint_after(delay);
+    }
+}
+
+impl std::fmt::Debug for RepaintHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RepaintHandle").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,439 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use egui::{
+    epaint::ImageDelta, ColorImage, DeferredViewportUiCallback, FullOutput, ImageData, TextureId,
+    TextureOptions, TexturesDelta, ViewportBuilder, ViewportCommand, ViewportId,
+};
+
+use super::repaint::RepaintSignal;
+
+/// The state that is shared between the root window and the windows of egui's deferred viewports.
+///
+/// Every window runs its own egui passes on the shared `egui::Context`, possibly on its own
+/// thread. Since a pass for one viewport may output textures and commands for any other viewport,
+/// those are routed through here.
+pub(crate) struct Viewports {
+    /// egui can only run one pass at a time.
+    pass_lock: Mutex<()>,
+    state: Mutex<ViewportsState>,
+}
+
+#[derive(Default)]
+struct ViewportsState {
+    /// A copy of every texture egui has allocated, so that windows that open later can upload all
+    /// of them and not just the changes since then.
+    textures: HashMap<TextureId, (Arc<ColorImage>, TextureOptions)>,
+    windows: HashMap<ViewportId, ViewportWindow>,
+}
+
+#[derive(Default)]
+struct ViewportWindow {
+    ui_cb: Option<Arc<DeferredViewportUiCallback>>,
+    repaint_signal: Option<Arc<RepaintSignal>>,
+    /// Commands for this viewport that were output by another viewport's pass.
+    commands: Vec<ViewportCommand>,
+    /// Texture changes that were output by other viewports' passes since this window's last pass.
+    textures_delta: TexturesDelta,
+    open: bool,
+    /// Set when egui stops showing the viewport, or when the root window closes.
+    close_requested: bool,
+    /// Set when the window was closed by the user or the host. egui is told about it with a final
+    /// pass for the viewport, but the root's output may still be from before that pass, so the
+    /// viewport is not opened again from that output.
+    closed: bool,
+}
+
+/// A deferred viewport that needs a new window.
+pub(crate) struct NewViewport {
+    pub id: ViewportId,
+    pub builder: ViewportBuilder,
+}
+
+impl Viewports {
+    pub fn new() -> Arc<Self> {
+        let mut state = ViewportsState::default();
+        state.windows.insert(
+            ViewportId::ROOT,
+            ViewportWindow {
+                open: true,
+                ..Default::default()
+            },
+        );
+
+        Arc::new(Self {
+            pass_lock: Mutex::new(()),
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Hold this while running an egui pass.
+    pub fn lock_pass(&self) -> MutexGuard<'_, ()> {
+        self.pass_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn state(&self) -> MutexGuard<'_, ViewportsState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Route the output of a pass that was run for `viewport_id`.
+    ///
+    /// The texture changes that other viewports made since the last pass are added to
+    /// `full_output`, and the commands for `viewport_id` are returned.
+    pub fn after_pass(
+        &self,
+        viewport_id: ViewportId,
+        full_output: &mut FullOutput,
+    ) -> Vec<ViewportCommand> {
+        let mut state = self.state();
+
+        state.mirror_textures(&full_output.textures_delta);
+        for (id, window) in &mut state.windows {
+            if *id != viewport_id && window.open {
+                window
+                    .textures_delta
+                    .append(full_output.textures_delta.clone());
+            }
+        }
+
+        let window = state.windows.entry(viewport_id).or_default();
+
+        let mut textures_delta = std::mem::take(&mut window.textures_delta);
+        textures_delta.append(std::mem::take(&mut full_output.textures_delta));
+        full_output.textures_delta = textures_delta;
+
+        let mut commands = std::mem::take(&mut window.commands);
+        for (id, output) in &mut full_output.viewport_output {
+            let output_commands = std::mem::take(&mut output.commands);
+            if *id == viewport_id {
+                commands.extend(output_commands);
+            } else if !output_commands.is_empty() {
+                state
+                    .windows
+                    .entry(*id)
+                    .or_default()
+                    .commands
+                    .extend(output_commands);
+            }
+        }
+
+        commands
+    }
+
+    /// Compare the deferred viewports in the output of a root pass to the open windows.
+    ///
+    /// Windows of viewports that egui stopped showing are asked to close, and the viewports that
+    /// don't have a window yet are returned.
+    pub fn sync_windows(&self, full_output: &FullOutput) -> Vec<NewViewport> {
+        let mut state = self.state();
+        let state = &mut *state;
+
+        let mut new_viewports = Vec::new();
+        for (id, output) in &full_output.viewport_output {
+            // Immediate viewports don't have a callback. They are always embedded.
+            let Some(ui_cb) = &output.viewport_ui_cb else {
+                continue;
+            };
+            if *id == ViewportId::ROOT {
+                continue;
+            }
+
+            let window = state.windows.entry(*id).or_default();
+            window.ui_cb = Some(Arc::clone(ui_cb));
+
+            if window.closed {
+                window.closed = false;
+            } else if !window.open {
+                window.open = true;
+                window.close_requested = false;
+                window.textures_delta = state.textures.iter().fold(
+                    TexturesDelta::default(),
+                    |mut delta, (id, (image, options))| {
+                        delta
+                            .set
+                            .push((*id, ImageDelta::full(Arc::clone(image), *options)));
+                        delta
+                    },
+                );
+
+                new_viewports.push(NewViewport {
+                    id: *id,
+                    builder: output.builder.clone(),
+                });
+            }
+        }
+
+        state.windows.retain(|id, window| {
+            if *id == ViewportId::ROOT || full_output.viewport_output.contains_key(id) {
+                return true;
+            }
+
+            window.close_requested = true;
+            window.open
+        });
+
+        new_viewports
+    }
+
+    pub fn set_repaint_signal(&self, viewport_id: ViewportId, repaint_signal: Arc<RepaintSignal>) {
+        self.state()
+            .windows
+            .entry(viewport_id)
+            .or_default()
+            .repaint_signal = Some(repaint_signal);
+    }
+
+    /// Wake the window of a deferred viewport. This is called from egui's repaint callback.
+    pub fn request_repaint_after(&self, viewport_id: ViewportId, delay: Duration) {
+        if let Some(repaint_signal) = self
+            .state()
+            .windows
+            .get(&viewport_id)
+            .and_then(|window| window.repaint_signal.as_ref())
+        {
+            repaint_signal.request_repaint_after(delay);
+        }
+    }
+
+    /// The callback that draws the UI of a deferred viewport.
+    pub fn ui_cb(&self, viewport_id: ViewportId) -> Option<Arc<DeferredViewportUiCallback>> {
+        self.state()
+            .windows
+            .get(&viewport_id)
+            .and_then(|window| window.ui_cb.clone())
+    }
+
+    /// Whether the window of a deferred viewport should close.
+    pub fn close_requested(&self, viewport_id: ViewportId) -> bool {
+        self.state()
+            .windows
+            .get(&viewport_id)
+            .is_none_or(|window| window.close_requested)
+    }
+
+    /// Called when a window is about to close.
+    ///
+    /// When the root window closes, the windows of all deferred viewports close with it. A
+    /// viewport whose window was closed is opened again if egui keeps showing it.
+    pub fn window_closed(&self, viewport_id: ViewportId) {
+        let mut state = self.state();
+
+        if viewport_id == ViewportId::ROOT {
+            for window in state.windows.values_mut() {
+                window.close_requested = true;
+            }
+        }
+
+        if let Some(window) = state.windows.get_mut(&viewport_id) {
+            window.open = false;
+            window.closed = true;
+            window.textures_delta = TexturesDelta::default();
+            window.repaint_signal = None;
+        }
+    }
+}
+
+impl ViewportsState {
+    fn mirror_textures(&mut self, textures_delta: &TexturesDelta) {
+        for (id, image_delta) in &textures_delta.set {
+            let ImageData::Color(image) = &image_delta.image;
+
+            match image_delta.pos {
+                None => {
+                    self.textures
+                        .insert(*id, (Arc::clone(image), image_delta.options));
+                }
+                Some([x, y]) => {
+                    let Some((texture, options)) = self.textures.get_mut(id) else {
+                        continue;
+                    };
+                    *options = image_delta.options;
+
+                    let texture = Arc::make_mut(texture);
+                    let [width, height] = image.size;
+                    for row in 0..height {
+                        let dst = (y + row) * texture.size[0] + x;
+                        let src = row * width;
+                        texture.pixels[dst..dst + width]
+                            .copy_from_slice(&image.pixels[src..src + width]);
+                    }
+                }
+            }
+        }
+
+        for id in &textures_delta.free {
+            self.textures.remove(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{Color32, ViewportClass, ViewportOutput};
+
+    use super::*;
+
+    fn viewport() -> ViewportId {
+        ViewportId::from_hash_of("viewport")
+    }
+
+    /// The output of a root pass that shows the given deferred viewports.
+    fn root_output(deferred: &[ViewportId]) -> FullOutput {
+        let mut full_output = FullOutput::default();
+        full_output
+            .viewport_output
+            .insert(ViewportId::ROOT, viewport_output(ViewportClass::Root, None));
+        for id in deferred {
+            let ui_cb: Arc<DeferredViewportUiCallback> = Arc::new(|_: &egui::Context| {});
+            full_output
+                .viewport_output
+                .insert(*id, viewport_output(ViewportClass::Deferred, Some(ui_cb)));
+        }
+
+        full_output
+    }
+
+    fn viewport_output(
+        class: ViewportClass,
+        viewport_ui_cb: Option<Arc<DeferredViewportUiCallback>>,
+    ) -> ViewportOutput {
+        ViewportOutput {
+            parent: ViewportId::ROOT,
+            class,
+            builder: ViewportBuilder::default(),
+            viewport_ui_cb,
+            commands: Vec::new(),
+            repaint_delay: Duration::ZERO,
+        }
+    }
+
+    fn opened(new_viewports: Vec<NewViewport>) -> Vec<ViewportId> {
+        new_viewports
+            .into_iter()
+            .map(|viewport| viewport.id)
+            .collect()
+    }
+
+    #[test]
+    fn partial_updates_are_mirrored() {
+        let id = TextureId::Managed(1);
+        let options = TextureOptions::default();
+        let mut state = ViewportsState::default();
+
+        state.mirror_textures(&TexturesDelta {
+            set: vec![(
+                id,
+                ImageDelta::full(ColorImage::filled([4, 3], Color32::BLACK), options),
+            )],
+            free: Vec::new(),
+        });
+        state.mirror_textures(&TexturesDelta {
+            set: vec![(
+                id,
+                ImageDelta::partial([1, 1], ColorImage::filled([2, 2], Color32::RED), options),
+            )],
+            free: Vec::new(),
+        });
+
+        let (texture, _) = &state.textures[&id];
+        assert_eq!(texture.size, [4, 3]);
+        for y in 0..3 {
+            for x in 0..4 {
+                let expected = if (1..3).contains(&x) && (1..3).contains(&y) {
+                    Color32::RED
+                } else {
+                    Color32::BLACK
+                };
+                assert_eq!(texture[(x, y)], expected, "pixel ({x}, {y})");
+            }
+        }
+
+        state.mirror_textures(&TexturesDelta {
+            set: Vec::new(),
+            free: vec![id],
+        });
+        assert!(state.textures.is_empty());
+    }
+
+    #[test]
+    fn new_windows_get_every_texture() {
+        let id = TextureId::Managed(1);
+        let viewports = Viewports::new();
+
+        let mut full_output = root_output(&[viewport()]);
+        full_output.textures_delta.set.push((
+            id,
+            ImageDelta::full(
+                ColorImage::filled([2, 2], Color32::RED),
+                TextureOptions::default(),
+            ),
+        ));
+        viewports.after_pass(ViewportId::ROOT, &mut full_output);
+        assert_eq!(opened(viewports.sync_windows(&full_output)), [viewport()]);
+
+        let mut full_output = FullOutput::default();
+        viewports.after_pass(viewport(), &mut full_output);
+        let set = &full_output.textures_delta.set;
+        assert_eq!(set.len(), 1);
+        assert_eq!(set[0].0, id);
+        assert_eq!(set[0].1.pos, None);
+    }
+
+    #[test]
+    fn windows_open_once_and_close_when_hidden() {
+        let viewports = Viewports::new();
+
+        assert_eq!(
+            opened(viewports.sync_windows(&root_output(&[viewport()]))),
+            [viewport()]
+        );
+        assert!(opened(viewports.sync_windows(&root_output(&[viewport()]))).is_empty());
+        assert!(!viewports.close_requested(viewport()));
+
+        // egui stopped showing the viewport.
+        assert!(opened(viewports.sync_windows(&root_output(&[]))).is_empty());
+        assert!(viewports.close_requested(viewport()));
+
+        viewports.window_closed(viewport());
+        viewports.sync_windows(&root_output(&[]));
+        assert!(viewports.close_requested(viewport()));
+
+        // Showing it again opens a new window.
+        assert_eq!(
+            opened(viewports.sync_windows(&root_output(&[viewport()]))),
+            [viewport()]
+        );
+        assert!(!viewports.close_requested(viewport()));
+    }
+
+    #[test]
+    fn closed_windows_reopen_only_when_shown_again() {
+        let viewports = Viewports::new();
+        viewports.sync_windows(&root_output(&[viewport()]));
+
+        // The root's output from before egui learned about the closed window doesn't reopen it.
+        viewports.window_closed(viewport());
+        assert!(opened(viewports.sync_windows(&root_output(&[viewport()]))).is_empty());
+
+        // The app kept showing the viewport after that.
+        assert_eq!(
+            opened(viewports.sync_windows(&root_output(&[viewport()]))),
+            [viewport()]
+        );
+    }
+
+    #[test]
+    fn closing_the_root_closes_every_window() {
+        let viewports = Viewports::new();
+        viewports.sync_windows(&root_output(&[viewport()]));
+        assert!(!viewports.close_requested(viewport()));
+
+        viewports.window_closed(ViewportId::ROOT);
+        assert!(viewports.close_requested(viewport()));
+    }
+}
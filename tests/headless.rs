@@ -0,0 +1,747 @@
+use std::path::PathBuf;
+
+use baseview::{
+    DropData, DropEffect, Event, EventStatus, MouseButton, MouseEvent, PhySize, Point, Size,
+    WindowEvent, WindowOpenOptions, WindowScalePolicy,
+};
+use egui::{vec2, ViewportCommand};
+use egui_baseview::{
+    Background, Clipboard, Code, HeadlessWindow, Key, KeyCapture, KeyCaptureStatus, KeyShortcut,
+    KeyboardEvent, MemoryClipboard, Modifiers, OffscreenRenderer, Queue,
+};
+use keyboard_types::KeyState;
+
+#[derive(Default)]
+struct TestState {
+    frames: u32,
+    text: String,
+    focus_text: bool,
+    close: bool,
+    resize: Option<PhySize>,
+    zoom_factor: Option<f32>,
+    commands: Vec<ViewportCommand>,
+    copy: Option<String>,
+    events: Vec<egui::Event>,
+    modifiers: egui::Modifiers,
+    hovered_files: Vec<PathBuf>,
+    dropped_files: Vec<PathBuf>,
+}
+
+fn update(egui_ctx: &egui::Context, queue: &mut Queue, state: &mut TestState) {
+    state.frames += 1;
+
+    egui_ctx.input(|input| {
+        state.events.extend(input.raw.events.iter().cloned());
+        state.modifiers = input.modifiers;
+        state.hovered_files = input
+            .raw
+            .hovered_files
+            .iter()
+            .filter_map(|file| file.path.clone())
+            .collect();
+        state.dropped_files.extend(
+            input
+                .raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone()),
+        );
+    });
+
+    egui::CentralPanel::default().show(egui_ctx, |ui| {
+        let response = ui.text_edit_singleline(&mut state.text);
+        if state.focus_text {
+            response.request_focus();
+        }
+    });
+
+    if state.close {
+        queue.close_window();
+    }
+    if let Some(physical_size) = state.resize.take() {
+        queue.resize(physical_size);
+    }
+    if let Some(zoom_factor) = state.zoom_factor.take() {
+        queue.set_zoom_factor(zoom_factor);
+    }
+    for command in state.commands.drain(..) {
+        egui_ctx.send_viewport_cmd(command);
+    }
+    if let Some(text) = state.copy.take() {
+        egui_ctx.copy_text(text);
+    }
+}
+
+/// A 200x100 window at a scale of 1, so that logical pixels, physical pixels and points are the
+/// same.
+fn headless_window(
+    build: impl FnMut(&egui::Context, &mut Queue, &mut TestState) + Send + 'static,
+) -> HeadlessWindow<
+    TestState,
+    impl FnMut(&egui::Context, &mut Queue, &mut TestState) + Send + 'static,
+> {
+    HeadlessWindow::new(&window_settings(), TestState::default(), build, update)
+}
+
+fn window_settings() -> WindowOpenOptions {
+    WindowOpenOptions {
+        title: String::from("egui-baseview headless test"),
+        size: Size::new(200.0, 100.0),
+        scale: WindowScalePolicy::ScaleFactor(1.0),
+        #[cfg(feature = "opengl")]
+        gl_config: None,
+    }
+}
+
+fn key_event(state: KeyState, key: Key, code: Code, modifiers: Modifiers) -> Event {
+    Event::Keyboard(KeyboardEvent {
+        state,
+        key,
+        code,
+        modifiers,
+        ..Default::default()
+    })
+}
+
+fn key_down(key: Key, code: Code, modifiers: Modifiers) -> Event {
+    key_event(KeyState::Down, key, code, modifiers)
+}
+
+fn character(c: &str) -> Key {
+    Key::Character(c.into())
+}
+
+fn cursor_moved(x: f64, y: f64, modifiers: Modifiers) -> Event {
+    Event::Mouse(MouseEvent::CursorMoved {
+        position: Point::new(x, y),
+        modifiers,
+    })
+}
+
+fn button_pressed(button: MouseButton) -> Event {
+    Event::Mouse(MouseEvent::ButtonPressed {
+        button,
+        modifiers: Modifiers::empty(),
+    })
+}
+
+fn button_released(button: MouseButton) -> Event {
+    Event::Mouse(MouseEvent::ButtonReleased {
+        button,
+        modifiers: Modifiers::empty(),
+    })
+}
+
+/// The modifier that egui's `command` is mapped to.
+fn command_modifier() -> Modifiers {
+    if cfg!(target_os = "macos") {
+        Modifiers::META
+    } else {
+        Modifiers::CONTROL
+    }
+}
+
+fn is_captured(status: EventStatus) -> bool {
+    matches!(status, EventStatus::Captured)
+}
+
+fn is_ignored(status: EventStatus) -> bool {
+    matches!(status, EventStatus::Ignored)
+}
+
+/// A window with the given key capture, where the text field has focus if `focus_text` is set.
+fn key_capture_window(
+    key_capture: KeyCapture,
+    focus_text: bool,
+) -> HeadlessWindow<
+    TestState,
+    impl FnMut(&egui::Context, &mut Queue, &mut TestState) + Send + 'static,
+> {
+    let mut window = headless_window(move |_egui_ctx, queue, state| {
+        queue.set_key_capture(key_capture.clone());
+        state.focus_text = focus_text;
+    });
+    window.step();
+    assert_eq!(window.egui_ctx().wants_keyboard_input(), focus_text);
+
+    window
+}
+
+#[test]
+fn update_changes_user_state() {
+    let mut window = headless_window(|_egui_ctx, _queue, state| state.text = "built".into());
+    assert_eq!(window.state().unwrap().frames, 0);
+    assert_eq!(window.state().unwrap().text, "built");
+
+    window.step();
+    window.step();
+    assert_eq!(window.state().unwrap().frames, 2);
+
+    window.state_mut().unwrap().text = "changed".into();
+    window.step();
+    assert_eq!(window.state().unwrap().text, "changed");
+}
+
+#[test]
+fn capture_all_only_captures_while_egui_wants_keyboard_input() {
+    let mut window = key_capture_window(KeyCapture::CaptureAll, false);
+    let status = window.send_event(key_down(character("a"), Code::KeyA, Modifiers::empty()));
+    assert!(is_ignored(status));
+
+    let mut window = key_capture_window(KeyCapture::CaptureAll, true);
+    let status = window.send_event(key_down(character("a"), Code::KeyA, Modifiers::empty()));
+    assert!(is_captured(status));
+}
+
+#[test]
+fn ignore_all_never_captures() {
+    let mut window = key_capture_window(KeyCapture::IgnoreAll, true);
+    let status = window.send_event(key_down(character("a"), Code::KeyA, Modifiers::empty()));
+    assert!(is_ignored(status));
+}
+
+#[test]
+fn capture_keys_only_captures_the_given_keys() {
+    let mut window = key_capture_window(KeyCapture::CaptureKeys(vec![character("a")]), true);
+
+    let status = window.send_event(key_down(character("a"), Code::KeyA, Modifiers::empty()));
+    assert!(is_captured(status));
+    let status = window.send_event(key_down(character("b"), Code::KeyB, Modifiers::empty()));
+    assert!(is_ignored(status));
+}
+
+#[test]
+fn ignore_keys_passes_the_given_keys_on() {
+    let mut window = key_capture_window(KeyCapture::IgnoreKeys(vec![character(" ")]), true);
+
+    let status = window.send_event(key_down(character(" "), Code::Space, Modifiers::empty()));
+    assert!(is_ignored(status));
+    let status = window.send_event(key_down(character("a"), Code::KeyA, Modifiers::empty()));
+    assert!(is_captured(status));
+}
+
+#[test]
+fn capture_shortcuts_matches_modifiers() {
+    let save = KeyShortcut::new(Modifiers::CONTROL, character("s"));
+    let mut window = key_capture_window(KeyCapture::CaptureShortcuts(vec![save]), false);
+
+    let status = window.send_event(key_down(character("s"), Code::KeyS, Modifiers::CONTROL));
+    assert!(is_captured(status));
+    let status = window.send_event(key_down(character("s"), Code::KeyS, Modifiers::empty()));
+    assert!(is_ignored(status));
+    let status = window.send_event(key_down(
+        character("S"),
+        Code::KeyS,
+        Modifiers::CONTROL | Modifiers::SHIFT,
+    ));
+    assert!(is_ignored(status));
+}
+
+#[test]
+fn ignore_shortcuts_passes_the_given_shortcuts_on() {
+    let space = KeyShortcut::key(character(" "));
+    let mut window = key_capture_window(KeyCapture::IgnoreShortcuts(vec![space]), false);
+
+    let status = window.send_event(key_down(character(" "), Code::Space, Modifiers::empty()));
+    assert!(is_ignored(status));
+    let status = window.send_event(key_down(character("a"), Code::KeyA, Modifiers::empty()));
+    assert!(is_captured(status));
+}
+
+#[test]
+fn custom_key_capture_decides_per_event() {
+    let key_capture = KeyCapture::custom(|event, _egui_ctx| {
+        if event.repeat {
+            KeyCaptureStatus::Ignored
+        } else {
+            KeyCaptureStatus::Captured
+        }
+    });
+    let mut window = key_capture_window(key_capture, false);
+
+    let status = window.send_event(key_down(character("a"), Code::KeyA, Modifiers::empty()));
+    assert!(is_captured(status));
+    let status = window.send_event(Event::Keyboard(KeyboardEvent {
+        state: KeyState::Down,
+        key: character("a"),
+        code: Code::KeyA,
+        repeat: true,
+        ..Default::default()
+    }));
+    assert!(is_ignored(status));
+}
+
+#[test]
+fn close_window_is_requested() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+    assert!(!window.step().requests.close);
+
+    window.state_mut().unwrap().close = true;
+    assert!(window.step().requests.close);
+}
+
+#[test]
+fn viewport_close_command_is_requested() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+    window.step();
+
+    window.state_mut().unwrap().commands = vec![ViewportCommand::Close];
+    assert!(window.step().requests.close);
+}
+
+#[test]
+fn inner_size_command_is_requested() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+    window.step();
+
+    window.state_mut().unwrap().commands = vec![ViewportCommand::InnerSize(vec2(300.0, 150.0))];
+    let frame = window.step();
+    assert_eq!(frame.requests.resize, Some(Size::new(300.0, 150.0)));
+}
+
+#[test]
+fn queue_resize_is_requested() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+    assert_eq!(window.step().requests.resize, None);
+
+    window.state_mut().unwrap().resize = Some(PhySize {
+        width: 400,
+        height: 200,
+    });
+    let frame = window.step();
+    assert_eq!(frame.requests.resize, Some(Size::new(400.0, 200.0)));
+    assert_eq!(
+        window.physical_size(),
+        PhySize {
+            width: 400,
+            height: 200
+        }
+    );
+}
+
+#[test]
+fn modifiers_follow_mouse_events() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+
+    window.send_event(cursor_moved(
+        10.0,
+        10.0,
+        Modifiers::CONTROL | Modifiers::SHIFT,
+    ));
+    window.step();
+    let modifiers = window.state().unwrap().modifiers;
+    assert!(modifiers.ctrl);
+    assert!(modifiers.shift);
+    assert!(!modifiers.alt);
+    assert_eq!(modifiers.command, !cfg!(target_os = "macos"));
+    assert!(!modifiers.mac_cmd);
+
+    window.send_event(cursor_moved(10.0, 10.0, Modifiers::empty()));
+    window.step();
+    assert_eq!(window.state().unwrap().modifiers, egui::Modifiers::NONE);
+}
+
+#[test]
+fn modifier_keys_apply_to_their_own_events() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+
+    window.send_event(key_down(Key::Shift, Code::ShiftLeft, Modifiers::empty()));
+    window.step();
+    assert!(window.state().unwrap().modifiers.shift);
+
+    window.send_event(key_event(
+        KeyState::Up,
+        Key::Shift,
+        Code::ShiftLeft,
+        Modifiers::SHIFT,
+    ));
+    window.step();
+    assert!(!window.state().unwrap().modifiers.shift);
+}
+
+#[test]
+fn modifiers_reset_when_unfocused() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+
+    window.send_event(key_down(
+        Key::Control,
+        Code::ControlLeft,
+        Modifiers::empty(),
+    ));
+    window.step();
+    assert!(window.state().unwrap().modifiers.ctrl);
+
+    // The key-up event never arrives.
+    window.send_event(Event::Window(WindowEvent::Unfocused));
+    window.step();
+    assert_eq!(window.state().unwrap().modifiers, egui::Modifiers::NONE);
+}
+
+fn ime_events(state: &TestState) -> Vec<egui::ImeEvent> {
+    state
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            egui::Event::Ime(ime_event) => Some(ime_event.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn dead_keys_compose_text() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+
+    window.send_event(key_down(Key::Dead, Code::Quote, Modifiers::empty()));
+    window.send_event(key_down(character("é"), Code::KeyE, Modifiers::empty()));
+    window.step();
+
+    assert_eq!(
+        ime_events(window.state().unwrap()),
+        [
+            egui::ImeEvent::Enabled,
+            egui::ImeEvent::Preedit(String::new()),
+            egui::ImeEvent::Commit(String::from("é")),
+            egui::ImeEvent::Disabled,
+        ]
+    );
+}
+
+#[test]
+fn other_keys_cancel_the_composition() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+
+    window.send_event(key_down(Key::Dead, Code::Quote, Modifiers::empty()));
+    window.send_event(key_down(Key::Shift, Code::ShiftLeft, Modifiers::empty()));
+    window.send_event(key_down(Key::Backspace, Code::Backspace, Modifiers::SHIFT));
+    window.step();
+
+    assert_eq!(
+        ime_events(window.state().unwrap()),
+        [
+            egui::ImeEvent::Enabled,
+            egui::ImeEvent::Preedit(String::new()),
+            egui::ImeEvent::Preedit(String::new()),
+            egui::ImeEvent::Disabled,
+        ]
+    );
+}
+
+#[test]
+fn losing_focus_cancels_the_composition() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+
+    window.send_event(key_down(Key::Dead, Code::Quote, Modifiers::empty()));
+    window.send_event(Event::Window(WindowEvent::Unfocused));
+    window.send_event(key_down(character("e"), Code::KeyE, Modifiers::empty()));
+    window.step();
+
+    let state = window.state().unwrap();
+    assert_eq!(
+        ime_events(state),
+        [
+            egui::ImeEvent::Enabled,
+            egui::ImeEvent::Preedit(String::new()),
+            egui::ImeEvent::Preedit(String::new()),
+            egui::ImeEvent::Disabled,
+        ]
+    );
+    assert!(state.events.contains(&egui::Event::Text(String::from("e"))));
+}
+
+#[test]
+fn dropped_files_reach_egui() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+    let files = vec![PathBuf::from("samples/kick.wav")];
+
+    let status = window.send_event(Event::Mouse(MouseEvent::DragEntered {
+        position: Point::new(10.0, 10.0),
+        modifiers: Modifiers::empty(),
+        data: DropData::Files(files.clone()),
+    }));
+    assert!(matches!(status, EventStatus::AcceptDrop(DropEffect::Copy)));
+    window.step();
+    assert_eq!(window.state().unwrap().hovered_files, files);
+
+    let status = window.send_event(Event::Mouse(MouseEvent::DragDropped {
+        position: Point::new(20.0, 10.0),
+        modifiers: Modifiers::empty(),
+        data: DropData::Files(files.clone()),
+    }));
+    assert!(matches!(status, EventStatus::AcceptDrop(DropEffect::Copy)));
+    window.step();
+    let state = window.state().unwrap();
+    assert!(state.hovered_files.is_empty());
+    assert_eq!(state.dropped_files, files);
+}
+
+#[test]
+fn drops_without_files_are_rejected() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+
+    let status = window.send_event(Event::Mouse(MouseEvent::DragEntered {
+        position: Point::new(10.0, 10.0),
+        modifiers: Modifiers::empty(),
+        data: DropData::None,
+    }));
+    assert!(is_ignored(status));
+}
+
+#[test]
+fn copy_goes_to_the_clipboard() {
+    let clipboard = MemoryClipboard::new();
+    let mut window = headless_window({
+        let clipboard = clipboard.clone();
+        move |_egui_ctx, queue, _state| queue.set_clipboard(clipboard.clone())
+    });
+
+    window.state_mut().unwrap().copy = Some("copied".into());
+    window.step();
+    assert_eq!(clipboard.text(), "copied");
+}
+
+#[test]
+fn paste_comes_from_the_clipboard() {
+    let mut clipboard = MemoryClipboard::new();
+    clipboard.set_text("pasted".into()).unwrap();
+    let mut window = headless_window({
+        let clipboard = clipboard.clone();
+        move |_egui_ctx, queue, _state| queue.set_clipboard(clipboard.clone())
+    });
+
+    window.send_event(key_down(character("v"), Code::KeyV, command_modifier()));
+    window.step();
+    assert!(window
+        .state()
+        .unwrap()
+        .events
+        .contains(&egui::Event::Text("pasted".into())));
+}
+
+#[test]
+fn middle_click_pastes_the_primary_selection() {
+    let mut clipboard = MemoryClipboard::new();
+    clipboard.set_primary("selected".into()).unwrap();
+    let mut window = headless_window({
+        let clipboard = clipboard.clone();
+        move |_egui_ctx, queue, _state| {
+            queue.set_clipboard(clipboard.clone());
+            queue.set_primary_selection(true);
+        }
+    });
+
+    window.send_event(cursor_moved(10.0, 10.0, Modifiers::empty()));
+    window.send_event(button_pressed(MouseButton::Middle));
+    window.step();
+    assert!(window
+        .state()
+        .unwrap()
+        .events
+        .contains(&egui::Event::Paste("selected".into())));
+}
+
+#[test]
+fn primary_selection_is_opt_in() {
+    let mut clipboard = MemoryClipboard::new();
+    clipboard.set_primary("selected".into()).unwrap();
+    let mut window = headless_window({
+        let clipboard = clipboard.clone();
+        move |_egui_ctx, queue, _state| queue.set_clipboard(clipboard.clone())
+    });
+
+    window.send_event(cursor_moved(10.0, 10.0, Modifiers::empty()));
+    window.send_event(button_pressed(MouseButton::Middle));
+    window.step();
+    assert!(!window
+        .state()
+        .unwrap()
+        .events
+        .iter()
+        .any(|event| matches!(event, egui::Event::Paste(_))));
+}
+
+#[test]
+fn drags_continue_outside_the_window() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+    let pointer_gone = |window: &HeadlessWindow<_, _>| {
+        window
+            .state()
+            .unwrap()
+            .events
+            .contains(&egui::Event::PointerGone)
+    };
+
+    window.send_event(cursor_moved(10.0, 10.0, Modifiers::empty()));
+    window.send_event(button_pressed(MouseButton::Left));
+    assert!(window.pointer_grabbed());
+    window.send_event(button_pressed(MouseButton::Right));
+    window.send_event(cursor_moved(-20.0, 10.0, Modifiers::empty()));
+    window.send_event(Event::Mouse(MouseEvent::CursorLeft));
+    window.step();
+    assert!(!pointer_gone(&window));
+    assert!(window
+        .state()
+        .unwrap()
+        .events
+        .contains(&egui::Event::PointerMoved(egui::pos2(-20.0, 10.0))));
+
+    // The grab is only released along with the last button.
+    window.send_event(button_released(MouseButton::Right));
+    assert!(window.pointer_grabbed());
+    window.send_event(button_released(MouseButton::Left));
+    assert!(!window.pointer_grabbed());
+    window.step();
+    assert!(pointer_gone(&window));
+}
+
+#[test]
+fn losing_focus_releases_the_pointer_grab() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+
+    window.send_event(cursor_moved(10.0, 10.0, Modifiers::empty()));
+    window.send_event(button_pressed(MouseButton::Left));
+    assert!(window.pointer_grabbed());
+
+    // The release of the button never arrives.
+    window.send_event(Event::Window(WindowEvent::Unfocused));
+    assert!(!window.pointer_grabbed());
+}
+
+#[test]
+fn cursor_left_without_a_held_button_ends_the_pointer() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+
+    window.send_event(cursor_moved(10.0, 10.0, Modifiers::empty()));
+    window.send_event(Event::Mouse(MouseEvent::CursorLeft));
+    window.step();
+    assert!(window
+        .state()
+        .unwrap()
+        .events
+        .contains(&egui::Event::PointerGone));
+}
+
+#[test]
+fn unchanged_frames_are_skipped() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+    // Let egui settle its layout first.
+    for _ in 0..5 {
+        window.step();
+    }
+    let stats = window.frame_stats();
+    assert!(stats.painted_frames > 0);
+
+    for _ in 0..2 {
+        window.repaint_handle().request_repaint();
+        assert!(!window.step().repainted);
+    }
+    assert_eq!(window.frame_stats().painted_frames, stats.painted_frames);
+    assert_eq!(
+        window.frame_stats().skipped_frames,
+        stats.skipped_frames + 2
+    );
+
+    window.state_mut().unwrap().text = String::from("changed");
+    window.repaint_handle().request_repaint();
+    assert!(window.step().repainted);
+    assert_eq!(
+        window.frame_stats().painted_frames,
+        stats.painted_frames + 1
+    );
+    assert_eq!(
+        window.frame_stats().skipped_frames,
+        stats.skipped_frames + 2
+    );
+}
+
+#[test]
+fn zoom_shortcuts_are_opt_in() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+    window.step();
+    assert!(!window
+        .egui_ctx()
+        .options(|options| options.zoom_with_keyboard));
+
+    let mut window = headless_window(|_egui_ctx, queue, _state| queue.set_zoom_mode(true));
+    window.step();
+    assert!(window
+        .egui_ctx()
+        .options(|options| options.zoom_with_keyboard));
+}
+
+#[test]
+fn zoom_factor_applies_from_the_next_pass() {
+    let mut window = headless_window(|_egui_ctx, _queue, _state| {});
+    window.step();
+
+    window.state_mut().unwrap().zoom_factor = Some(2.0);
+    let frame = window.step();
+    assert_eq!(frame.full_output.pixels_per_point, 1.0);
+    assert_eq!(window.pixels_per_point(), 1.0);
+    assert_eq!(frame.requests.resize, None);
+
+    let frame = window.step();
+    assert_eq!(frame.full_output.pixels_per_point, 2.0);
+    assert_eq!(window.pixels_per_point(), 2.0);
+    // The window is only resized in zoom mode.
+    assert_eq!(frame.requests.resize, None);
+}
+
+#[test]
+fn zoom_mode_resizes_the_window() {
+    let mut window = headless_window(|_egui_ctx, queue, _state| queue.set_zoom_mode(true));
+    window.step();
+
+    window.state_mut().unwrap().zoom_factor = Some(2.0);
+    window.step();
+    let frame = window.step();
+    assert_eq!(frame.full_output.pixels_per_point, 2.0);
+    assert_eq!(frame.requests.resize, Some(Size::new(400.0, 200.0)));
+    assert_eq!(
+        window.physical_size(),
+        PhySize {
+            width: 400,
+            height: 200
+        }
+    );
+}
+
+#[test]
+fn the_background_is_painted_behind_the_ui() {
+    let mut window = HeadlessWindow::new(
+        &window_settings(),
+        (),
+        |_egui_ctx, queue, _state| {
+            queue.set_background(Some(Background::LinearGradient {
+                start: egui::Color32::RED,
+                end: egui::Color32::BLUE,
+                angle: 0.0,
+            }));
+        },
+        |_egui_ctx, _queue, _state| {},
+    );
+    let mut renderer = OffscreenRenderer::new();
+
+    let mut full_output = window.step().full_output;
+    // The background isn't one of egui's shapes.
+    assert!(full_output.shapes.is_empty());
+    let image = renderer.render(
+        window.background(),
+        window.physical_size(),
+        full_output.pixels_per_point,
+        window.egui_ctx(),
+        &mut full_output,
+    );
+
+    let left = image[(0, 50)];
+    let right = image[(199, 50)];
+    assert!(left.r() > 240 && left.b() < 15, "{left:?}");
+    assert!(right.b() > 240 && right.r() < 15, "{right:?}");
+
+    // An unchanged background doesn't make the frame change.
+    for _ in 0..5 {
+        window.step();
+    }
+    window.repaint_handle().request_repaint();
+    assert!(!window.step().repainted);
+}